@@ -24,8 +24,9 @@ bitflags! {
     }
 }
 
-#[derive(Primitive, Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Primitive, Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum Relationship {
+    #[default]
     Neutral = 0b00,
     Allied = 0b01,
     Hostile = 0b10,
@@ -36,7 +37,7 @@ pub mod components {
 
     use bevy::color::palettes::css::*;
 
-    #[derive(Primitive, Debug, Clone, Copy, PartialEq, Eq, Default, Component)]
+    #[derive(Primitive, Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Component)]
     pub enum Faction {
         #[default]
         A = 0b00000001,
@@ -68,6 +69,16 @@ pub mod components {
             })
         }
 
+        /// Position of this faction among all factions, from 0 to 7.
+        pub fn index(self) -> usize {
+            (self as u8).trailing_zeros() as usize
+        }
+
+        /// The flag of this faction in a set of [`Factions`].
+        pub fn flag(self) -> Factions {
+            Factions::from_bits_retain(self as u8)
+        }
+
         pub fn color(self) -> Color {
             match self {
                 Faction::A => RED,
@@ -96,17 +107,38 @@ pub mod resources {
     #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Resource)]
     pub struct FactionRelationships {
         relationships: u64,
+        /// Of each faction with units without a faction, 2 bits per [`Faction::index`].
+        unaligned: u16,
+        /// Between units without a faction.
+        among_unaligned: Relationship,
     }
 
     impl FactionRelationships {
         pub fn new() -> Self {
-            FactionRelationships { relationships: 0 }
+            Self::default()
         }
 
         pub fn with_default(default: Relationship) -> Self {
             let value = default as u64;
             let relationships = (0..28).fold(0u64, |acc, i| acc | (value << (i * 2)));
-            FactionRelationships { relationships }
+            FactionRelationships {
+                relationships,
+                ..Self::default()
+            }
+        }
+
+        /// Set the relationship of every faction with units without a faction.
+        pub fn with_unaligned(mut self, relationship: Relationship) -> Self {
+            for faction in Faction::iter_once() {
+                self.set_unaligned_relationship(faction, relationship);
+            }
+            self
+        }
+
+        /// Set the relationship between units without a faction.
+        pub fn with_among_unaligned(mut self, relationship: Relationship) -> Self {
+            self.among_unaligned = relationship;
+            self
         }
 
         pub fn from_mapping(
@@ -141,7 +173,10 @@ pub mod resources {
             faction2: Faction,
             relationship: Relationship,
         ) {
-            let (index, shift) = self.get_index_and_shift(faction1, faction2);
+            // A faction is always allied with itself.
+            let Some(shift) = Self::get_shift(faction1, faction2) else {
+                return;
+            };
             let value = relationship as u64;
 
             // Clear the existing bits
@@ -150,9 +185,41 @@ pub mod resources {
             self.relationships |= value << shift;
         }
 
+        /// A faction is always [`Relationship::Allied`] with itself.
         pub fn get_relationship(&self, faction1: Faction, faction2: Faction) -> Relationship {
-            let (index, shift) = self.get_index_and_shift(faction1, faction2);
-            match (self.relationships >> shift) & 0b11 {
+            let Some(shift) = Self::get_shift(faction1, faction2) else {
+                return Relationship::Allied;
+            };
+            Self::decode(self.relationships >> shift)
+        }
+
+        pub fn set_unaligned_relationship(&mut self, faction: Faction, relationship: Relationship) {
+            let shift = faction.index() * 2;
+            self.unaligned &= !(0b11 << shift);
+            self.unaligned |= (relationship as u16) << shift;
+        }
+
+        pub fn get_unaligned_relationship(&self, faction: Faction) -> Relationship {
+            Self::decode(u64::from(self.unaligned >> (faction.index() * 2)))
+        }
+
+        /// Relationship between units that may lack a faction.
+        pub fn relationship_between(
+            &self,
+            faction1: Option<Faction>,
+            faction2: Option<Faction>,
+        ) -> Relationship {
+            match (faction1, faction2) {
+                (Some(faction1), Some(faction2)) => self.get_relationship(faction1, faction2),
+                (Some(faction), None) | (None, Some(faction)) => {
+                    self.get_unaligned_relationship(faction)
+                }
+                (None, None) => self.among_unaligned,
+            }
+        }
+
+        fn decode(bits: u64) -> Relationship {
+            match bits & 0b11 {
                 0 => Relationship::Neutral,
                 1 => Relationship::Allied,
                 2 => Relationship::Hostile,
@@ -160,24 +227,20 @@ pub mod resources {
             }
         }
 
-        fn get_index_and_shift(
-            &self,
-            mut faction1: Faction,
-            mut faction2: Faction,
-        ) -> (usize, usize) {
-            // Ensure faction1 < faction2
-            if (faction1 as u8) > (faction2 as u8) {
-                std::mem::swap(&mut faction1, &mut faction2);
-            }
-
-            let f1 = faction1 as u8;
-            let f2 = faction2 as u8;
+        /// Bit offset of the relationship between two distinct factions.
+        fn get_shift(faction1: Faction, faction2: Faction) -> Option<usize> {
+            let (f1, f2) = (faction1.index(), faction2.index());
+            // Ensure f1 < f2
+            let (f1, f2) = match f1.cmp(&f2) {
+                std::cmp::Ordering::Less => (f1, f2),
+                std::cmp::Ordering::Greater => (f2, f1),
+                std::cmp::Ordering::Equal => return None,
+            };
 
-            // Calculate the index in the lower triangular matrix
-            let index = (f1 * 7 + f2 - 1) - (f1 * (f1 + 1) / 2);
-            let shift = index * 2;
+            // Calculate the index in the upper triangular matrix of 8 factions
+            let index = f1 * (15 - f1) / 2 + (f2 - f1 - 1);
 
-            (index as usize, shift as usize)
+            Some(index * 2)
         }
     }
 }
@@ -186,6 +249,13 @@ pub mod resources {
 pub struct AllegiencePlugin {
     faction_relationships: resources::FactionRelationships,
 }
+impl AllegiencePlugin {
+    pub fn new(faction_relationships: resources::FactionRelationships) -> Self {
+        Self {
+            faction_relationships,
+        }
+    }
+}
 
 impl Plugin for AllegiencePlugin {
     fn build(&self, app: &mut App) {
@@ -199,6 +269,8 @@ pub mod prelude {
     pub use super::components::*;
     pub use super::resources::*;
 
+    pub use super::{Factions, Relationship};
+
     pub use super::AllegiencePlugin;
 }
 
@@ -239,4 +311,121 @@ mod tests {
 
         assert_eq!(factions.next(), None);
     }
+
+    #[test]
+    fn relationships_are_independent_and_symmetric() {
+        use resources::*;
+
+        let pairs: Vec<_> = Faction::iter_once()
+            .flat_map(|a| Faction::iter_once().map(move |b| (a, b)))
+            .filter(|(a, b)| a.index() < b.index())
+            .collect();
+        let relationship = |i: usize| match i % 3 {
+            0 => Relationship::Neutral,
+            1 => Relationship::Allied,
+            _ => Relationship::Hostile,
+        };
+        let fr = FactionRelationships::from_mapping(
+            pairs
+                .iter()
+                .enumerate()
+                .map(|(i, &pair)| (pair, relationship(i))),
+        );
+
+        for (i, &(a, b)) in pairs.iter().enumerate() {
+            assert_eq!(fr.get_relationship(a, b), relationship(i));
+            assert_eq!(fr.get_relationship(b, a), relationship(i));
+        }
+        assert_eq!(
+            FactionRelationships::with_default(Relationship::Hostile)
+                .get_relationship(Faction::C, Faction::C),
+            Relationship::Allied
+        );
+    }
+
+    #[test]
+    fn lookups_among_first_factions_unchanged() {
+        use resources::*;
+
+        // The only factions the raw flag indexing could address without overflowing.
+        let first = [Faction::A, Faction::B, Faction::C, Faction::D];
+        let pairs = || {
+            first
+                .into_iter()
+                .flat_map(move |a| first.into_iter().map(move |b| (a, b)))
+                .filter(|(a, b)| a != b)
+        };
+
+        for (a, b) in pairs() {
+            assert_eq!(
+                FactionRelationships::new().get_relationship(a, b),
+                Relationship::Neutral
+            );
+            for relationship in [
+                Relationship::Neutral,
+                Relationship::Allied,
+                Relationship::Hostile,
+            ] {
+                assert_eq!(
+                    FactionRelationships::with_default(relationship).get_relationship(a, b),
+                    relationship
+                );
+            }
+
+            let mut fr = FactionRelationships::with_default(Relationship::Allied);
+            fr.set_relationship(a, b, Relationship::Hostile);
+            for (c, d) in pairs() {
+                let expected = if (c, d) == (a, b) || (c, d) == (b, a) {
+                    Relationship::Hostile
+                } else {
+                    Relationship::Allied
+                };
+                assert_eq!(fr.get_relationship(c, d), expected);
+            }
+        }
+
+        let fr = FactionRelationships::from_closure(|a, b| match (a, b) {
+            (Faction::A, _) => Relationship::Hostile,
+            (_, Faction::D) => Relationship::Neutral,
+            _ => Relationship::Allied,
+        });
+        assert_eq!(
+            fr.get_relationship(Faction::B, Faction::A),
+            Relationship::Hostile
+        );
+        assert_eq!(
+            fr.get_relationship(Faction::D, Faction::C),
+            Relationship::Neutral
+        );
+        assert_eq!(
+            fr.get_relationship(Faction::B, Faction::C),
+            Relationship::Allied
+        );
+
+        // Unlike with the raw flag indexing, a faction no longer reads a stored slot for itself.
+        let fr = FactionRelationships::with_default(Relationship::Hostile);
+        for faction in first {
+            assert_eq!(fr.get_relationship(faction, faction), Relationship::Allied);
+        }
+    }
+
+    #[test]
+    fn unaligned_relationships() {
+        use resources::*;
+
+        let mut fr = FactionRelationships::with_default(Relationship::Hostile)
+            .with_unaligned(Relationship::Neutral)
+            .with_among_unaligned(Relationship::Allied);
+        fr.set_unaligned_relationship(Faction::B, Relationship::Hostile);
+
+        let between = |a, b| fr.relationship_between(a, b);
+        assert_eq!(between(Some(Faction::A), None), Relationship::Neutral);
+        assert_eq!(between(None, Some(Faction::B)), Relationship::Hostile);
+        assert_eq!(between(None, Some(Faction::H)), Relationship::Neutral);
+        assert_eq!(between(None, None), Relationship::Allied);
+        assert_eq!(
+            between(Some(Faction::A), Some(Faction::B)),
+            Relationship::Hostile
+        );
+    }
 }
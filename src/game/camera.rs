@@ -17,8 +17,13 @@ pub mod systems {
         player: Query<&Position, With<LocalPlayer>>,
         mouse: Res<MousePosition>,
     ) {
-        let (camera, mut transform, global) = camera.single_mut();
-        let player_pos = player.single().0;
+        let Ok((camera, mut transform, global)) = camera.get_single_mut() else {
+            return;
+        };
+        // Keep the focus while there is no local player.
+        let Ok(player_pos) = player.get_single().map(|pos| pos.0) else {
+            return;
+        };
         let mouse_viewport_pos = mouse.0;
 
         let Some(mouse_world) = camera.viewport_to_world_2d(global, mouse_viewport_pos) else {
@@ -0,0 +1,549 @@
+//! Combat feedback: health bars over units, and floating damage and heal numbers.
+//!
+//! Standalone, so it can be left out, e.g. for performance tests:
+//! `GamePlugins.build().disable::<CombatFeedbackPlugin>()`.
+
+use std::time::Duration;
+
+use bevy::color::palettes::css::{AQUA, BLACK, LIME, WHITE};
+use bevy::prelude::*;
+use bevy::sprite::{Anchor, MaterialMesh2dBundle, Mesh2dHandle};
+use bevy::text::{Text2dBounds, TextLayoutInfo};
+
+use super::allegience::prelude::*;
+use super::kinematic::prelude::*;
+use super::pool::prelude::*;
+use super::unit::prelude::*;
+
+use crate::mouse::MousePosition;
+
+pub mod components {
+    use super::*;
+
+    /// A part of the health bar of `unit`, kept above it.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Component)]
+    pub struct HealthBar {
+        pub unit: Entity,
+        /// The part showing the HP left, rather than the background.
+        pub(super) fill: bool,
+    }
+
+    /// The parts of a unit's health bar, despawned along with it.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Component)]
+    pub struct HealthBarParts(pub(super) [Entity; 2]);
+
+    /// A number rising from a unit while fading out.
+    #[derive(Debug, Clone, PartialEq, Eq, Component)]
+    pub struct FloatingNumber {
+        pub timer: Timer,
+    }
+
+    impl PoolKind for FloatingNumber {
+        const NAME: &'static str = "floating_number";
+        /// The text, so it isn't laid out anew for every number.
+        type Keep = (
+            Text,
+            Anchor,
+            Text2dBounds,
+            TextLayoutInfo,
+            Transform,
+            GlobalTransform,
+            Visibility,
+            InheritedVisibility,
+            ViewVisibility,
+        );
+    }
+}
+
+pub mod resources {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub enum HealthBarVisibility {
+        #[default]
+        Always,
+        /// Only below full HP.
+        Damaged,
+        /// Only under the cursor.
+        Hovered,
+        DamagedOrHovered,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Resource)]
+    pub struct CombatFeedbackSettings {
+        pub health_bars: HealthBarVisibility,
+        /// Width of health bars per unit of [`Radius`].
+        pub bar_width: f32,
+        pub bar_height: f32,
+        /// Space between a unit and its health bar.
+        pub bar_gap: f32,
+        /// Show floating damage and heal numbers.
+        pub numbers: bool,
+        pub number_duration: Duration,
+        /// How fast floating numbers rise.
+        pub number_rise: f32,
+        pub font_size: f32,
+    }
+    impl Default for CombatFeedbackSettings {
+        fn default() -> Self {
+            Self {
+                health_bars: HealthBarVisibility::default(),
+                bar_width: 2.5,
+                bar_height: 2.,
+                bar_gap: 4.,
+                numbers: true,
+                number_duration: Duration::from_millis(800),
+                number_rise: 40.,
+                font_size: 14.,
+            }
+        }
+    }
+
+    /// Mesh and materials shared by every health bar.
+    #[derive(Debug, Resource)]
+    pub struct FeedbackAssets {
+        /// Unit square, scaled to the size of the bar.
+        pub mesh: Mesh2dHandle,
+        pub background: Handle<ColorMaterial>,
+        /// One per [`Faction`], by [`Faction::index`].
+        pub fills: Vec<Handle<ColorMaterial>>,
+        pub unaligned: Handle<ColorMaterial>,
+    }
+    impl FeedbackAssets {
+        pub fn fill(&self, faction: Option<Faction>) -> Handle<ColorMaterial> {
+            faction
+                .map_or(&self.unaligned, |faction| &self.fills[faction.index()])
+                .clone()
+        }
+    }
+    impl FromWorld for FeedbackAssets {
+        fn from_world(world: &mut World) -> Self {
+            let mesh = world
+                .resource_mut::<Assets<Mesh>>()
+                .add(Rectangle::new(1., 1.));
+            let mut materials = world.resource_mut::<Assets<ColorMaterial>>();
+            let fills = Faction::iter_once()
+                .map(|faction| materials.add(faction.color()))
+                .collect();
+            Self {
+                mesh: Mesh2dHandle(mesh),
+                background: materials.add(Color::from(BLACK).with_alpha(0.6)),
+                fills,
+                unaligned: materials.add(Faction::color_opt(None)),
+            }
+        }
+    }
+}
+
+pub(super) mod systems {
+    use super::*;
+    use components::*;
+    use resources::*;
+
+    pub fn spawn_health_bar(
+        trigger: Trigger<OnAdd, HP>,
+        mut commands: Commands,
+        query: Query<Option<&Faction>>,
+        assets: Res<FeedbackAssets>,
+    ) {
+        let unit = trigger.entity();
+        let Ok(faction) = query.get(unit) else {
+            return;
+        };
+        let parts = [
+            (false, assets.background.clone(), 10.),
+            (true, assets.fill(faction.copied()), 10.1),
+        ]
+        .map(|(fill, material, z)| {
+            commands
+                .spawn((
+                    HealthBar { unit, fill },
+                    MaterialMesh2dBundle {
+                        mesh: assets.mesh.clone(),
+                        material,
+                        transform: Transform::from_xyz(0., 0., z),
+                        visibility: Visibility::Hidden,
+                        ..default()
+                    },
+                ))
+                .id()
+        });
+        commands.entity(unit).insert(HealthBarParts(parts));
+    }
+
+    pub fn despawn_health_bar(
+        trigger: Trigger<OnRemove, HP>,
+        mut commands: Commands,
+        query: Query<&HealthBarParts>,
+    ) {
+        let Ok(HealthBarParts(parts)) = query.get(trigger.entity()) else {
+            return;
+        };
+        for &part in parts {
+            if let Some(mut part) = commands.get_entity(part) {
+                part.despawn();
+            }
+        }
+    }
+
+    /// Recolor the fill of units whose [`Faction`] changed, as the units themselves are.
+    pub fn update_health_bar_fill(
+        mut bars: Query<&mut Handle<ColorMaterial>, With<HealthBar>>,
+        units: Query<(&HealthBarParts, Option<&Faction>)>,
+        changed: Query<Entity, (With<HealthBarParts>, Changed<Faction>)>,
+        mut removed: RemovedComponents<Faction>,
+        assets: Res<FeedbackAssets>,
+    ) {
+        for entity in changed.iter().chain(removed.read()) {
+            let Ok((HealthBarParts([_, fill]), faction)) = units.get(entity) else {
+                continue;
+            };
+            let Ok(mut material) = bars.get_mut(*fill) else {
+                continue;
+            };
+            let wanted = assets.fill(faction.copied());
+            if *material != wanted {
+                *material = wanted;
+            }
+        }
+    }
+
+    #[allow(clippy::type_complexity)]
+    pub fn update_health_bars(
+        mut bars: Query<(&HealthBar, &mut Transform, &mut Visibility)>,
+        units: Query<
+            (
+                &Position,
+                &HP,
+                Option<&Radius>,
+                Option<&Visibility>,
+                Has<Dead>,
+            ),
+            Without<HealthBar>,
+        >,
+        camera: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+        mouse: Option<Res<MousePosition>>,
+        settings: Res<CombatFeedbackSettings>,
+    ) {
+        let cursor = camera
+            .get_single()
+            .ok()
+            .zip(mouse)
+            .and_then(|((camera, global), mouse)| camera.viewport_to_world_2d(global, mouse.0));
+
+        for (bar, mut transform, mut visibility) in bars.iter_mut() {
+            let Ok((pos, hp, radius, unit_visibility, dead)) = units.get(bar.unit) else {
+                continue;
+            };
+            let radius = radius.map_or(1., |radius| radius.0);
+            let damaged = hp.value < hp.max;
+            let hovered = cursor.is_some_and(|cursor| cursor.distance(pos.0) <= radius);
+            let shown = !dead
+                && unit_visibility != Some(&Visibility::Hidden)
+                && match settings.health_bars {
+                    HealthBarVisibility::Always => true,
+                    HealthBarVisibility::Damaged => damaged,
+                    HealthBarVisibility::Hovered => hovered,
+                    HealthBarVisibility::DamagedOrHovered => damaged || hovered,
+                };
+            visibility.set_if_neq(if shown {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            });
+            if !shown {
+                continue;
+            }
+
+            let width = settings.bar_width * radius;
+            let fraction = if hp.max > 0. {
+                (hp.value / hp.max).clamp(0., 1.)
+            } else {
+                0.
+            };
+            // The fill shrinks towards the left end of the bar.
+            let (x, scale) = if bar.fill {
+                (-width * (1. - fraction) / 2., width * fraction)
+            } else {
+                (0., width)
+            };
+            let y = radius + settings.bar_gap + settings.bar_height / 2.;
+            transform.translation = (pos.0 + Vec2::new(x, y)).extend(transform.translation.z);
+            transform.scale = Vec3::new(scale, settings.bar_height, 1.);
+        }
+    }
+
+    /// Damage to shields is shown apart from damage to HP, next to it.
+    /// Reused numbers keep their text, which is only updated.
+    pub fn spawn_floating_numbers(
+        mut commands: Commands,
+        mut hits: EventReader<UnitWasHit>,
+        mut heals: EventReader<UnitHealed>,
+        units: Query<(&Position, Option<&Radius>)>,
+        mut idle: Query<&mut Text, With<Pooled>>,
+        mut pool: ResMut<Pool<FloatingNumber>>,
+        settings: Res<CombatFeedbackSettings>,
+    ) {
+        let shield_offset = Vec2::X * settings.font_size;
+        let numbers = hits
+            .read()
+            .flat_map(|hit| {
+                [
+                    (hit.entity, hit.amount, Color::from(WHITE), Vec2::ZERO),
+                    (
+                        hit.entity,
+                        hit.absorbed.shield,
+                        Color::from(AQUA),
+                        shield_offset,
+                    ),
+                ]
+            })
+            .chain(
+                heals
+                    .read()
+                    .map(|heal| (heal.entity, heal.amount, Color::from(LIME), Vec2::ZERO)),
+            );
+        for (entity, amount, color, offset) in numbers {
+            if !settings.numbers || amount <= 0. {
+                continue;
+            }
+            let Ok((pos, radius)) = units.get(entity) else {
+                continue;
+            };
+            let top = pos.0 + Vec2::Y * radius.map_or(0., |radius| radius.0) + offset;
+            let value = format!("{:.0}", amount.ceil());
+            let transform = Transform::from_translation(top.extend(20.));
+            let mut number = pool.spawn(
+                &mut commands,
+                (
+                    transform,
+                    FloatingNumber {
+                        timer: Timer::new(settings.number_duration, TimerMode::Once),
+                    },
+                ),
+            );
+            match idle.get_mut(number.id()) {
+                Ok(mut text) => {
+                    let section = &mut text.sections[0];
+                    section.value = value;
+                    section.style.color = color;
+                    section.style.font_size = settings.font_size;
+                }
+                Err(_) => {
+                    number.insert(Text2dBundle {
+                        text: Text::from_section(
+                            value,
+                            TextStyle {
+                                font_size: settings.font_size,
+                                color,
+                                ..default()
+                            },
+                        ),
+                        transform,
+                        ..default()
+                    });
+                }
+            }
+        }
+    }
+
+    pub fn float_numbers(
+        mut commands: Commands,
+        mut query: Query<(Entity, &mut FloatingNumber, &mut Transform, &mut Text)>,
+        settings: Res<CombatFeedbackSettings>,
+        time: Res<Time>,
+    ) {
+        for (entity, mut number, mut transform, mut text) in query.iter_mut() {
+            if number.timer.tick(time.delta()).finished() {
+                commands.entity(entity).recycle::<FloatingNumber>();
+                continue;
+            }
+            transform.translation.y += settings.number_rise * time.delta_seconds();
+            let alpha = number.timer.fraction_remaining();
+            for section in text.sections.iter_mut() {
+                section.style.color.set_alpha(alpha);
+            }
+        }
+    }
+}
+
+pub struct CombatFeedbackPlugin;
+
+impl Plugin for CombatFeedbackPlugin {
+    fn build(&self, app: &mut App) {
+        use components::*;
+        use resources::*;
+        use systems::*;
+
+        app.add_plugins(PoolPlugin::<FloatingNumber>::new(256))
+            .init_resource::<CombatFeedbackSettings>()
+            .init_resource::<FeedbackAssets>()
+            .observe(spawn_health_bar)
+            .observe(despawn_health_bar)
+            .add_systems(
+                Update,
+                (
+                    update_health_bar_fill,
+                    update_health_bars.after(KinematicSet::Sync),
+                ),
+            )
+            .add_systems(Update, (spawn_floating_numbers, float_numbers).chain());
+    }
+}
+
+pub mod prelude {
+    pub use super::components::*;
+    pub use super::resources::*;
+
+    pub use super::CombatFeedbackPlugin;
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    use components::*;
+    use resources::*;
+
+    fn world(health_bars: HealthBarVisibility) -> World {
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world.init_resource::<Assets<Mesh>>();
+        world.init_resource::<Assets<ColorMaterial>>();
+        world.init_resource::<FeedbackAssets>();
+        world.insert_resource(CombatFeedbackSettings {
+            health_bars,
+            ..default()
+        });
+        world.insert_resource(Pool::<FloatingNumber>::new(8));
+        world.init_resource::<Events<UnitWasHit>>();
+        world.init_resource::<Events<UnitHealed>>();
+        world.observe(systems::spawn_health_bar);
+        world.observe(systems::despawn_health_bar);
+        world
+    }
+
+    fn spawn_unit(world: &mut World) -> (Entity, [Entity; 2]) {
+        let unit = world
+            .spawn((Position(Vec2::ZERO), Radius(4.), Faction::A))
+            .insert(HP::full(10.))
+            .id();
+        world.flush();
+        (unit, world.get::<HealthBarParts>(unit).unwrap().0)
+    }
+
+    #[test]
+    fn health_bars_shown_when_damaged() {
+        let mut world = world(HealthBarVisibility::Damaged);
+        let (unit, [background, fill]) = spawn_unit(&mut world);
+        world.run_system_once(systems::update_health_bars);
+        for part in [background, fill] {
+            assert_eq!(world.get::<Visibility>(part), Some(&Visibility::Hidden));
+        }
+
+        world.get_mut::<HP>(unit).unwrap().damage(7.5);
+        world.run_system_once(systems::update_health_bars);
+        for part in [background, fill] {
+            assert_eq!(world.get::<Visibility>(part), Some(&Visibility::Inherited));
+        }
+        // A quarter of the bar, 2.5 wide per unit of radius, from its left end.
+        let transform = world.get::<Transform>(fill).unwrap();
+        assert_eq!(transform.scale.x, 2.5);
+        assert_eq!(transform.translation.x, -3.75);
+        assert_eq!(world.get::<Transform>(background).unwrap().scale.x, 10.);
+
+        world.entity_mut(unit).insert(Dead);
+        world.run_system_once(systems::update_health_bars);
+        assert_eq!(world.get::<Visibility>(fill), Some(&Visibility::Hidden));
+
+        world.entity_mut(unit).remove::<HP>();
+        world.flush();
+        assert!(world.get_entity(background).is_none());
+        assert!(world.get_entity(fill).is_none());
+    }
+
+    #[test]
+    fn health_bar_fill_follows_the_faction() {
+        let mut world = world(HealthBarVisibility::Always);
+        let (unit, [_, fill]) = spawn_unit(&mut world);
+        let fill_of = |world: &World| world.get::<Handle<ColorMaterial>>(fill).unwrap().clone();
+        let assets = world.resource::<FeedbackAssets>();
+        let (a, b, unaligned) = (
+            assets.fill(Some(Faction::A)),
+            assets.fill(Some(Faction::B)),
+            assets.fill(None),
+        );
+        assert_eq!(fill_of(&world), a);
+
+        world.entity_mut(unit).insert(Faction::B);
+        world.run_system_once(systems::update_health_bar_fill);
+        assert_eq!(fill_of(&world), b);
+
+        world.entity_mut(unit).remove::<Faction>();
+        world.run_system_once(systems::update_health_bar_fill);
+        assert_eq!(fill_of(&world), unaligned);
+    }
+
+    #[test]
+    fn floating_numbers_are_reused() {
+        let mut world = world(HealthBarVisibility::Always);
+        let (unit, _) = spawn_unit(&mut world);
+
+        world.send_event(UnitWasHit {
+            entity: unit,
+            source: None,
+            amount: 4.2,
+            kind: DamageKind::default(),
+            absorbed: Absorbed::default(),
+        });
+        world.run_system_once(systems::spawn_floating_numbers);
+        let mut numbers = world.query_filtered::<(Entity, &Text), With<FloatingNumber>>();
+        let (first, text) = numbers.single(&world);
+        assert_eq!(text.sections[0].value, "5");
+        assert_eq!(text.sections[0].style.color, Color::from(WHITE));
+
+        world.resource_mut::<Events<UnitWasHit>>().clear();
+
+        let duration = world.resource::<CombatFeedbackSettings>().number_duration;
+        world.resource_mut::<Time>().advance_by(duration);
+        world.run_system_once(systems::float_numbers);
+        assert!(world.get::<Pooled>(first).is_some());
+
+        world.send_event(UnitHealed {
+            entity: unit,
+            amount: 3.,
+        });
+        world.run_system_once(systems::spawn_floating_numbers);
+        let (second, text) = numbers.single(&world);
+        assert_eq!(second, first);
+        assert_eq!(text.sections[0].value, "3");
+        assert_eq!(text.sections[0].style.color, Color::from(LIME));
+        let stats = world.resource::<Pool<FloatingNumber>>().stats();
+        assert_eq!((stats.spawned, stats.reused), (1, 1));
+    }
+
+    #[test]
+    fn shield_damage_shown_apart() {
+        let mut world = world(HealthBarVisibility::Always);
+        let (unit, _) = spawn_unit(&mut world);
+
+        // Entirely absorbed by the shield.
+        world.send_event(UnitWasHit {
+            entity: unit,
+            source: None,
+            amount: 0.,
+            kind: DamageKind::default(),
+            absorbed: Absorbed {
+                shield: 6.,
+                armor: 0.,
+            },
+        });
+        world.run_system_once(systems::spawn_floating_numbers);
+        let mut numbers = world.query_filtered::<&Text, With<FloatingNumber>>();
+        let text = numbers.single(&world);
+        assert_eq!(text.sections[0].value, "6");
+        assert_eq!(text.sections[0].style.color, Color::from(AQUA));
+    }
+}
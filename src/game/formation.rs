@@ -0,0 +1,436 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+
+use super::kinematic::prelude::*;
+use super::order::prelude::*;
+
+use components::FormationShape;
+
+/// Offsets of the slots of a formation around its center, facing the x-axis.
+pub fn slots(shape: FormationShape, count: usize, spacing: f32) -> Vec<Vec2> {
+    let centered = |i: usize, n: usize| (i as f32 - (n as f32 - 1.) / 2.) * spacing;
+    match shape {
+        FormationShape::Line => (0..count)
+            .map(|i| Vec2::new(0., centered(i, count)))
+            .collect(),
+        FormationShape::Column => (0..count)
+            .map(|i| Vec2::new(-centered(i, count), 0.))
+            .collect(),
+        FormationShape::Wedge => {
+            let depth = (count / 2) as f32 * spacing;
+            (0..count)
+                .map(|i| {
+                    let row = i.div_ceil(2) as f32;
+                    let side = if i % 2 == 0 { 1. } else { -1. };
+                    Vec2::new(depth / 2. - row * spacing, side * row * spacing)
+                })
+                .collect()
+        }
+        FormationShape::Circle => {
+            if count < 2 {
+                return vec![Vec2::ZERO; count];
+            }
+            let radius = (spacing * count as f32 / TAU).max(spacing / 2.);
+            (0..count)
+                .map(|i| Vec2::from_angle(i as f32 * TAU / count as f32) * radius)
+                .collect()
+        }
+        FormationShape::Box => {
+            let columns = (count as f32).sqrt().ceil() as usize;
+            let rows = count.div_ceil(columns.max(1));
+            (0..count)
+                .map(|i| Vec2::new(-centered(i / columns, rows), centered(i % columns, columns)))
+                .collect()
+        }
+    }
+}
+
+/// Assign every row to a distinct column with the minimal total cost,
+/// using the Hungarian algorithm on a square cost matrix.
+pub fn assign(cost: &[Vec<f32>]) -> Vec<usize> {
+    let n = cost.len();
+    // Potentials and matching, 1-based with 0 as a sentinel.
+    let mut u = vec![0.; n + 1];
+    let mut v = vec![0.; n + 1];
+    let mut row_of = vec![0; n + 1];
+    let mut way = vec![0; n + 1];
+
+    for row in 1..=n {
+        row_of[0] = row;
+        let mut col = 0;
+        let mut min = vec![f32::INFINITY; n + 1];
+        let mut used = vec![false; n + 1];
+        loop {
+            used[col] = true;
+            let current = row_of[col];
+            let mut delta = f32::INFINITY;
+            let mut next = 0;
+            for j in 1..=n {
+                if used[j] {
+                    continue;
+                }
+                let reduced = cost[current - 1][j - 1] - u[current] - v[j];
+                if reduced < min[j] {
+                    min[j] = reduced;
+                    way[j] = col;
+                }
+                if min[j] < delta {
+                    delta = min[j];
+                    next = j;
+                }
+            }
+            for j in 0..=n {
+                if used[j] {
+                    u[row_of[j]] += delta;
+                    v[j] -= delta;
+                } else {
+                    min[j] -= delta;
+                }
+            }
+            col = next;
+            if row_of[col] == 0 {
+                break;
+            }
+        }
+        while col != 0 {
+            let prev = way[col];
+            row_of[col] = row_of[prev];
+            col = prev;
+        }
+    }
+
+    let mut assignment = vec![0; n];
+    for (col, &row) in row_of.iter().enumerate().skip(1) {
+        assignment[row - 1] = col - 1;
+    }
+    assignment
+}
+
+pub mod components {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub enum FormationShape {
+        /// Side by side.
+        #[default]
+        Line,
+        /// One behind the other.
+        Column,
+        /// A V with its tip at the front.
+        Wedge,
+        Circle,
+        /// A square grid.
+        Box,
+    }
+
+    /// A unit moving to its slot in a formation,
+    /// paced to the slowest member of its group.
+    #[derive(Debug, Clone, Copy, PartialEq, Component)]
+    pub struct FormationMember {
+        pub slot: Vec2,
+        /// Lowest [`SelfMoving::accel`] of the group, as its [`MaxAcceleration`].
+        pub pace: f32,
+        /// Lowest [`MaxSpeed`] of the group, as its own, if any member has one.
+        pub pace_speed: Option<f32>,
+        /// [`MaxAcceleration`] to restore once the slot is reached.
+        pub(super) restore: Option<f32>,
+        /// [`MaxSpeed`] to restore once the slot is reached.
+        pub(super) restore_speed: Option<f32>,
+    }
+}
+
+pub mod events {
+    use super::*;
+    use components::*;
+
+    /// Order a group of units to a destination in formation.
+    #[derive(Debug, Clone, Event)]
+    pub struct FormationOrder {
+        pub members: Vec<Entity>,
+        pub dest: Vec2,
+        pub shape: FormationShape,
+        /// Distance between neighboring slots.
+        pub spacing: f32,
+        /// Queue the move after the members' current orders.
+        pub append: bool,
+    }
+}
+
+pub mod systems {
+    use super::*;
+    use components::*;
+    use events::*;
+
+    #[allow(clippy::type_complexity)]
+    pub fn issue_formation_orders(
+        mut commands: Commands,
+        mut events: EventReader<FormationOrder>,
+        mut members: Query<(
+            &Position,
+            &SelfMoving,
+            Option<&mut Orders>,
+            Option<&MaxAcceleration>,
+            Option<&MaxSpeed>,
+            Option<&FormationMember>,
+        )>,
+    ) {
+        for order in events.read() {
+            let group: Vec<_> = order
+                .members
+                .iter()
+                .filter_map(|&entity| {
+                    let (pos, self_moving, _, _, max_speed, member) = members.get(entity).ok()?;
+                    // The speed of members already paced is the one they had before.
+                    let speed = match member {
+                        Some(member) => member.restore_speed,
+                        None => max_speed.map(|max_speed| max_speed.0),
+                    };
+                    Some((entity, pos.0, self_moving.accel, speed))
+                })
+                .collect();
+            if group.is_empty() {
+                continue;
+            }
+
+            let center = group.iter().map(|(_, pos, ..)| *pos).sum::<Vec2>() / group.len() as f32;
+            let facing = (order.dest - center).normalize_or(Vec2::X);
+            let slots: Vec<_> = slots(order.shape, group.len(), order.spacing)
+                .into_iter()
+                .map(|offset| order.dest + facing.rotate(offset))
+                .collect();
+            let cost: Vec<Vec<f32>> = group
+                .iter()
+                .map(|(_, pos, ..)| slots.iter().map(|slot| pos.distance(*slot)).collect())
+                .collect();
+            let pace = group
+                .iter()
+                .map(|(_, _, accel, _)| *accel)
+                .fold(f32::INFINITY, f32::min);
+            let pace_speed = group
+                .iter()
+                .filter_map(|(.., speed)| *speed)
+                .reduce(f32::min);
+
+            for ((entity, ..), slot) in group.iter().zip(assign(&cost)) {
+                let Ok((_, _, orders, max_acc, max_speed, member)) = members.get_mut(*entity)
+                else {
+                    continue;
+                };
+                let slot = slots[slot];
+                match orders {
+                    Some(mut orders) => orders.issue(Order::MoveTo(slot), order.append),
+                    None => {
+                        commands
+                            .entity(*entity)
+                            .insert(Orders::new(Order::MoveTo(slot)));
+                    }
+                }
+                let (restore, restore_speed) = match member {
+                    Some(member) => (member.restore, member.restore_speed),
+                    None => (
+                        max_acc.map(|max_acc| max_acc.0),
+                        max_speed.map(|max_speed| max_speed.0),
+                    ),
+                };
+                let mut entity = commands.entity(*entity);
+                entity.insert((
+                    FormationMember {
+                        slot,
+                        pace,
+                        pace_speed,
+                        restore,
+                        restore_speed,
+                    },
+                    MaxAcceleration(pace),
+                ));
+                if let Some(speed) = pace_speed {
+                    entity.insert(MaxSpeed(speed));
+                }
+            }
+        }
+    }
+
+    /// Release members whose slot is no longer among their orders,
+    /// because it was reached or the orders were replaced.
+    pub fn release_formation_members(
+        mut commands: Commands,
+        query: Query<(Entity, &FormationMember, Option<&Orders>)>,
+    ) {
+        for (entity, member, orders) in query.iter() {
+            let moving_to_slot = orders.is_some_and(|orders| {
+                orders
+                    .iter()
+                    .any(|order| *order == Order::MoveTo(member.slot))
+            });
+            if moving_to_slot {
+                continue;
+            }
+
+            let mut entity = commands.entity(entity);
+            entity.remove::<FormationMember>();
+            match member.restore {
+                Some(max_acc) => entity.insert(MaxAcceleration(max_acc)),
+                None => entity.remove::<MaxAcceleration>(),
+            };
+            match member.restore_speed {
+                Some(max_speed) => entity.insert(MaxSpeed(max_speed)),
+                None => entity.remove::<MaxSpeed>(),
+            };
+        }
+    }
+}
+
+pub struct FormationPlugin;
+
+impl Plugin for FormationPlugin {
+    fn build(&self, app: &mut App) {
+        use events::*;
+        use systems::*;
+
+        app.add_event::<FormationOrder>().add_systems(
+            Update,
+            // Release first, not to undo the pacing of members given a new order.
+            (release_formation_members, issue_formation_orders)
+                .chain()
+                .before(KinematicSet::Steering),
+        );
+    }
+}
+
+pub mod prelude {
+    pub use super::components::*;
+    pub use super::events::*;
+
+    pub use super::FormationPlugin;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use components::*;
+    use events::*;
+
+    #[test]
+    fn slots_per_shape() {
+        for shape in [
+            FormationShape::Line,
+            FormationShape::Column,
+            FormationShape::Wedge,
+            FormationShape::Circle,
+            FormationShape::Box,
+        ] {
+            for count in 0..10 {
+                let slots = slots(shape, count, 10.);
+
+                assert_eq!(slots.len(), count);
+                for (i, a) in slots.iter().enumerate() {
+                    for b in &slots[i + 1..] {
+                        assert!(a.distance(*b) >= 5., "{shape:?} with {count} overlaps");
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn assignment_minimises_total_cost() {
+        let cost = vec![vec![4., 1., 3.], vec![2., 0., 5.], vec![3., 2., 2.]];
+
+        // Greedily giving row 1 its cheapest column first would cost 0 + 3 + 3 = 6.
+        assert_eq!(assign(&cost), vec![1, 0, 2]);
+    }
+
+    #[test]
+    fn paced_to_the_slowest_until_released() {
+        use bevy::ecs::system::RunSystemOnce;
+
+        let mut world = World::new();
+        world.init_resource::<Events<FormationOrder>>();
+        let fast = world
+            .spawn((
+                Position(Vec2::ZERO),
+                SelfMoving { accel: 100. },
+                MaxSpeed(50.),
+            ))
+            .id();
+        let slow = world
+            .spawn((
+                Position(Vec2::new(10., 0.)),
+                SelfMoving { accel: 20. },
+                MaxSpeed(10.),
+            ))
+            .id();
+        let unbounded = world
+            .spawn((Position(Vec2::new(20., 0.)), SelfMoving { accel: 60. }))
+            .id();
+        world.send_event(FormationOrder {
+            members: vec![fast, slow, unbounded],
+            dest: Vec2::new(100., 0.),
+            shape: FormationShape::Line,
+            spacing: 10.,
+            append: false,
+        });
+        world.run_system_once(systems::issue_formation_orders);
+
+        for entity in [fast, slow, unbounded] {
+            assert_eq!(world.get::<MaxAcceleration>(entity).unwrap().0, 20.);
+            assert_eq!(world.get::<MaxSpeed>(entity).unwrap().0, 10.);
+            world.get_mut::<Orders>(entity).unwrap().clear();
+        }
+        world.run_system_once(systems::release_formation_members);
+
+        assert_eq!(world.get::<MaxSpeed>(fast).unwrap().0, 50.);
+        assert_eq!(world.get::<MaxSpeed>(slow).unwrap().0, 10.);
+        assert!(world.get::<MaxSpeed>(unbounded).is_none());
+        assert!(world.get::<MaxAcceleration>(fast).is_none());
+    }
+
+    #[test]
+    fn reissued_order_keeps_the_pacing() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins).add_plugins(FormationPlugin);
+        let world = app.world_mut();
+        let fast = world
+            .spawn((
+                Position(Vec2::ZERO),
+                SelfMoving { accel: 100. },
+                MaxSpeed(50.),
+            ))
+            .id();
+        let slow = world
+            .spawn((
+                Position(Vec2::new(10., 0.)),
+                SelfMoving { accel: 20. },
+                MaxSpeed(10.),
+            ))
+            .id();
+        let order = |dest| FormationOrder {
+            members: vec![fast, slow],
+            dest,
+            shape: FormationShape::Line,
+            spacing: 10.,
+            append: false,
+        };
+        world.send_event(order(Vec2::new(100., 0.)));
+        app.update();
+
+        let dest = Vec2::new(0., 100.);
+        app.world_mut().send_event(order(dest));
+        app.update();
+        app.update();
+
+        let world = app.world();
+        for entity in [fast, slow] {
+            let member = world.get::<FormationMember>(entity).unwrap();
+            assert!(member.slot.distance(dest) <= 10.);
+            assert_eq!(world.get::<MaxSpeed>(entity).unwrap().0, 10.);
+            assert_eq!(world.get::<MaxAcceleration>(entity).unwrap().0, 20.);
+        }
+        assert_eq!(
+            world.get::<FormationMember>(fast).unwrap().restore_speed,
+            Some(50.)
+        );
+    }
+}
@@ -0,0 +1,201 @@
+//! Rendering of [`Height`]: sprites lifted and scaled up as they rise, over a shadow.
+//!
+//! Kept out of the [`KinematicPlugin`], which stays headless.
+
+use bevy::{
+    prelude::*,
+    sprite::{MaterialMesh2dBundle, Mesh2dHandle},
+};
+
+use super::kinematic::prelude::*;
+use super::pool::prelude::*;
+
+pub mod components {
+    use super::*;
+
+    /// The shadow of an entity with a [`Height`].
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Component)]
+    pub struct HeightShadow {
+        pub owner: Entity,
+    }
+
+    impl PoolKind for HeightShadow {
+        const NAME: &'static str = "height_shadow";
+        /// The sprite, shared by every shadow.
+        type Keep = (
+            Mesh2dHandle,
+            Handle<ColorMaterial>,
+            Transform,
+            GlobalTransform,
+            Visibility,
+            InheritedVisibility,
+            ViewVisibility,
+        );
+    }
+}
+
+pub mod resources {
+    use super::*;
+
+    /// How height is rendered.
+    #[derive(Debug, Clone, PartialEq, Resource)]
+    pub struct HeightRendering {
+        /// Vertical screen offset per unit of height.
+        pub lift: f32,
+        /// Growth of the scale per unit of height.
+        pub scale: f32,
+        pub shadow_color: Color,
+    }
+    impl Default for HeightRendering {
+        fn default() -> Self {
+            Self {
+                lift: 1.,
+                scale: 0.004,
+                shadow_color: Color::srgba(0., 0., 0., 0.4),
+            }
+        }
+    }
+
+    /// Shared assets of every [`HeightShadow`](super::components::HeightShadow).
+    #[derive(Debug, Resource)]
+    pub struct ShadowAssets {
+        pub mesh: Mesh2dHandle,
+        pub material: Handle<ColorMaterial>,
+    }
+    impl FromWorld for ShadowAssets {
+        fn from_world(world: &mut World) -> Self {
+            let color = world.resource::<HeightRendering>().shadow_color;
+            let mesh = world
+                .resource_mut::<Assets<Mesh>>()
+                .add(Ellipse::new(0.5, 0.3));
+            let material = world.resource_mut::<Assets<ColorMaterial>>().add(color);
+            Self {
+                mesh: Mesh2dHandle(mesh),
+                material,
+            }
+        }
+    }
+}
+
+pub mod systems {
+    use super::*;
+    use components::*;
+    use resources::*;
+
+    pub fn spawn_height_shadows(
+        mut commands: Commands,
+        query: Query<Entity, Added<Height>>,
+        idle: Query<(), With<Pooled>>,
+        mut pool: ResMut<Pool<HeightShadow>>,
+        assets: Res<ShadowAssets>,
+    ) {
+        for owner in query.iter() {
+            let mut shadow = pool.spawn(&mut commands, HeightShadow { owner });
+            // Reused shadows keep their sprite.
+            if !idle.contains(shadow.id()) {
+                shadow.insert(MaterialMesh2dBundle {
+                    mesh: assets.mesh.clone(),
+                    material: assets.material.clone(),
+                    ..default()
+                });
+            }
+        }
+    }
+
+    pub fn sync_height_shadows(
+        mut commands: Commands,
+        mut shadows: Query<(Entity, &HeightShadow, &mut Transform)>,
+        owners: Query<(&Position, &Height, Option<&CrossSectionSize>)>,
+        rendering: Res<HeightRendering>,
+    ) {
+        for (entity, shadow, mut transform) in shadows.iter_mut() {
+            let Ok((pos, height, size)) = owners.get(shadow.owner) else {
+                commands.entity(entity).recycle::<HeightShadow>();
+                continue;
+            };
+            let size = size.map_or(1., |s| s.0);
+            // Shadows shrink as their owner rises.
+            let scale = size / (1. + height.value * rendering.scale);
+            transform.translation = pos.0.extend(-0.5);
+            transform.scale = Vec3::new(scale, scale, 1.);
+        }
+    }
+
+    /// Offset the transforms synchronized from [`Position`] by the height.
+    pub fn sync_height_transform(
+        mut query: Query<(&Height, &mut Transform)>,
+        rendering: Res<HeightRendering>,
+    ) {
+        for (height, mut transform) in query.iter_mut() {
+            let scale = 1. + height.value * rendering.scale;
+            transform.translation.y += height.value * rendering.lift;
+            transform.scale = Vec3::new(scale, scale, 1.);
+        }
+    }
+}
+
+pub struct HeightRenderPlugin;
+
+impl Plugin for HeightRenderPlugin {
+    fn build(&self, app: &mut App) {
+        use components::*;
+        use resources::*;
+        use systems::*;
+
+        app.add_plugins(PoolPlugin::<HeightShadow>::new(256))
+            .init_resource::<HeightRendering>()
+            .init_resource::<ShadowAssets>()
+            .add_systems(
+                Update,
+                (
+                    spawn_height_shadows,
+                    (sync_height_transform, sync_height_shadows).after(KinematicSet::Sync),
+                ),
+            );
+    }
+}
+
+pub mod prelude {
+    pub use super::components::*;
+    pub use super::resources::*;
+
+    pub use super::HeightRenderPlugin;
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    use components::*;
+    use resources::*;
+
+    #[test]
+    fn shadows_are_reused() {
+        let mut world = World::new();
+        world.init_resource::<Assets<Mesh>>();
+        world.init_resource::<Assets<ColorMaterial>>();
+        world.init_resource::<HeightRendering>();
+        world.init_resource::<ShadowAssets>();
+        world.insert_resource(Pool::<HeightShadow>::new(8));
+
+        let mut shadows = Vec::new();
+        for _ in 0..2 {
+            let owner = world
+                .spawn((Position(Vec2::ZERO), Height::launched(1.)))
+                .id();
+            world.run_system_once(systems::spawn_height_shadows);
+            let mut query = world.query_filtered::<Entity, With<HeightShadow>>();
+            shadows.push(query.single(&world));
+
+            world.despawn(owner);
+            world.run_system_once(systems::sync_height_shadows);
+        }
+
+        assert_eq!(shadows[0], shadows[1]);
+        let stats = world.resource::<Pool<HeightShadow>>().stats();
+        assert_eq!((stats.spawned, stats.reused, stats.recycled), (1, 1, 2));
+        assert!(world.get::<Mesh2dHandle>(shadows[0]).is_some());
+    }
+}
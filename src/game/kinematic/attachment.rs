@@ -0,0 +1,229 @@
+//! Positions relative to a moving parent, e.g. turrets, orbiting shields or carried items.
+
+use bevy::prelude::*;
+
+use super::components::*;
+
+/// Longest chain of attachments that is resolved, guarding against cycles.
+const MAX_DEPTH: usize = 16;
+
+pub mod components {
+    use super::*;
+
+    /// Keep this entity at an offset from a parent entity's [`Position`],
+    /// rotated by the parent's [`Heading`] if it has one.
+    /// Detached once the parent is despawned, keeping its last position and velocity.
+    ///
+    /// Projectiles spawned from an attached muzzle can inherit its [`Velocity`].
+    ///
+    /// Prerequisite: [`Position`]
+    #[derive(Debug, Clone, Copy, PartialEq, Component)]
+    pub struct Attached {
+        pub parent: Entity,
+        /// Offset from the parent, in the parent's frame.
+        pub offset: Vec2,
+        /// Copy the parent's velocity into this entity's [`Velocity`].
+        pub inherit_velocity: bool,
+    }
+    impl Attached {
+        pub fn new(parent: Entity, offset: Vec2) -> Self {
+            Self {
+                parent,
+                offset,
+                inherit_velocity: true,
+            }
+        }
+        pub fn without_velocity(mut self) -> Self {
+            self.inherit_velocity = false;
+            self
+        }
+    }
+}
+
+pub(super) mod systems {
+    use super::*;
+    use components::*;
+
+    /// Resolve the world position and velocity of an attached entity,
+    /// walking up its chain of parents.
+    fn resolve(
+        entity: Entity,
+        attachments: &Query<(Entity, &Attached)>,
+        bodies: &Query<(&mut Position, Option<&mut Velocity>)>,
+        headings: &Query<&Heading>,
+    ) -> Option<(Vec2, Vec2)> {
+        let mut offset = Vec2::ZERO;
+        let mut current = entity;
+        for _ in 0..MAX_DEPTH {
+            let Ok((_, attached)) = attachments.get(current) else {
+                let (pos, vel) = bodies.get(current).ok()?;
+                return Some((pos.0 + offset, vel.map_or(Vec2::ZERO, |v| v.0)));
+            };
+            offset += headings
+                .get(attached.parent)
+                .map_or(attached.offset, |heading| {
+                    heading.dir().rotate(attached.offset)
+                });
+            current = attached.parent;
+        }
+        None
+    }
+
+    pub fn resolve_attachments(
+        mut commands: Commands,
+        attachments: Query<(Entity, &Attached)>,
+        mut bodies: Query<(&mut Position, Option<&mut Velocity>)>,
+        headings: Query<&Heading>,
+    ) {
+        let resolved: Vec<_> = attachments
+            .iter()
+            .filter_map(|(entity, attached)| {
+                let state = resolve(entity, &attachments, &bodies, &headings);
+                if state.is_none() {
+                    commands.entity(entity).remove::<Attached>();
+                }
+                state.map(|state| (entity, attached.inherit_velocity, state))
+            })
+            .collect();
+
+        for (entity, inherit_velocity, (pos, vel)) in resolved {
+            let Ok((mut position, velocity)) = bodies.get_mut(entity) else {
+                continue;
+            };
+            position.0 = pos;
+            if let (true, Some(mut velocity)) = (inherit_velocity, velocity) {
+                velocity.0 = vel;
+            }
+        }
+    }
+}
+
+pub mod prelude {
+    pub use super::components::*;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    use components::*;
+
+    #[test]
+    fn offset_rotated_by_parent_heading() {
+        let mut world = World::new();
+        let parent = world
+            .spawn((
+                Position(Vec2::new(10., 0.)),
+                Velocity(Vec2::new(3., 4.)),
+                Heading(FRAC_PI_2),
+            ))
+            .id();
+        let turret = world
+            .spawn((
+                Position(Vec2::ZERO),
+                Velocity::default(),
+                Attached::new(parent, Vec2::new(5., 0.)),
+            ))
+            .id();
+        let decal = world
+            .spawn((
+                Position(Vec2::ZERO),
+                Velocity::default(),
+                Attached::new(parent, Vec2::new(0., 2.)).without_velocity(),
+            ))
+            .id();
+        world.run_system_once(systems::resolve_attachments);
+
+        assert!(world
+            .get::<Position>(turret)
+            .unwrap()
+            .0
+            .abs_diff_eq(Vec2::new(10., 5.), 1e-5));
+        assert_eq!(world.get::<Velocity>(turret).unwrap().0, Vec2::new(3., 4.));
+        assert!(world
+            .get::<Position>(decal)
+            .unwrap()
+            .0
+            .abs_diff_eq(Vec2::new(8., 0.), 1e-5));
+        assert_eq!(world.get::<Velocity>(decal).unwrap().0, Vec2::ZERO);
+    }
+
+    #[test]
+    fn chains_add_up() {
+        let mut world = World::new();
+        let root = world.spawn((Position(Vec2::ZERO), Velocity(Vec2::X))).id();
+        // Turned by its own heading, which rotates the offsets attached to it.
+        let arm = world
+            .spawn((
+                Position(Vec2::ZERO),
+                Heading(FRAC_PI_2),
+                Attached::new(root, Vec2::new(10., 0.)),
+            ))
+            .id();
+        let hand = world
+            .spawn((
+                Position(Vec2::ZERO),
+                Velocity::default(),
+                Attached::new(arm, Vec2::new(5., 0.)),
+            ))
+            .id();
+        world.run_system_once(systems::resolve_attachments);
+
+        assert!(world
+            .get::<Position>(arm)
+            .unwrap()
+            .0
+            .abs_diff_eq(Vec2::new(10., 0.), 1e-5));
+        assert!(world
+            .get::<Position>(hand)
+            .unwrap()
+            .0
+            .abs_diff_eq(Vec2::new(10., 5.), 1e-5));
+        assert_eq!(world.get::<Velocity>(hand).unwrap().0, Vec2::X);
+    }
+
+    #[test]
+    fn cycles_are_detached() {
+        let mut world = World::new();
+        let a = world.spawn(Position(Vec2::new(1., 0.))).id();
+        let b = world
+            .spawn((Position(Vec2::new(2., 0.)), Attached::new(a, Vec2::X)))
+            .id();
+        world.entity_mut(a).insert(Attached::new(b, Vec2::X));
+        world.run_system_once(systems::resolve_attachments);
+
+        for (entity, x) in [(a, 1.), (b, 2.)] {
+            assert!(world.get::<Attached>(entity).is_none());
+            assert_eq!(world.get::<Position>(entity).unwrap().0, Vec2::new(x, 0.));
+        }
+    }
+
+    #[test]
+    fn detached_when_the_parent_despawns() {
+        let mut world = World::new();
+        let parent = world
+            .spawn((Position(Vec2::new(10., 0.)), Velocity(Vec2::Y)))
+            .id();
+        let child = world
+            .spawn((
+                Position(Vec2::ZERO),
+                Velocity::default(),
+                Attached::new(parent, Vec2::X),
+            ))
+            .id();
+        world.run_system_once(systems::resolve_attachments);
+        assert_eq!(world.get::<Position>(child).unwrap().0, Vec2::new(11., 0.));
+
+        world.despawn(parent);
+        world.run_system_once(systems::resolve_attachments);
+
+        // Left where it was, still moving as the parent last did.
+        assert!(world.get::<Attached>(child).is_none());
+        assert_eq!(world.get::<Position>(child).unwrap().0, Vec2::new(11., 0.));
+        assert_eq!(world.get::<Velocity>(child).unwrap().0, Vec2::Y);
+    }
+}
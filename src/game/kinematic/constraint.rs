@@ -0,0 +1,263 @@
+//! Springs, ropes and distance joints between two kinematic entities.
+
+use bevy::prelude::*;
+
+use super::components::*;
+
+pub mod components {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum ConstraintKind {
+        /// A damped spring pulling towards its rest length.
+        Spring {
+            rest_length: f32,
+            stiffness: f32,
+            damping: f32,
+        },
+        /// A rope that only pulls once stretched past its length.
+        Rope { max_length: f32 },
+        /// A rigid rod keeping a fixed distance.
+        Distance { length: f32 },
+    }
+
+    /// Link this entity to another one.
+    /// Entities without [`Velocity`] act as fixed anchors, everything else is weighted by [`Mass`].
+    /// The constraint is removed once the other entity is despawned.
+    ///
+    /// Prerequisite: [`Position`]
+    #[derive(Debug, Clone, Copy, PartialEq, Component)]
+    pub struct Constraint {
+        pub other: Entity,
+        pub kind: ConstraintKind,
+        /// Break once the constraint force exceeds this.
+        pub break_force: Option<f32>,
+    }
+    impl Constraint {
+        pub fn new(other: Entity, kind: ConstraintKind) -> Self {
+            Self {
+                other,
+                kind,
+                break_force: None,
+            }
+        }
+        pub fn spring(other: Entity, rest_length: f32, stiffness: f32, damping: f32) -> Self {
+            Self::new(
+                other,
+                ConstraintKind::Spring {
+                    rest_length,
+                    stiffness,
+                    damping,
+                },
+            )
+        }
+        pub fn rope(other: Entity, max_length: f32) -> Self {
+            Self::new(other, ConstraintKind::Rope { max_length })
+        }
+        pub fn distance(other: Entity, length: f32) -> Self {
+            Self::new(other, ConstraintKind::Distance { length })
+        }
+
+        pub fn with_break_force(mut self, break_force: f32) -> Self {
+            self.break_force = Some(break_force);
+            self
+        }
+    }
+}
+
+pub mod events {
+    use super::*;
+    use components::*;
+
+    /// A [`Constraint`] broke and was removed.
+    #[derive(Debug, Event)]
+    pub struct ConstraintBroken {
+        pub entity: Entity,
+        pub other: Entity,
+        pub kind: ConstraintKind,
+        pub force: f32,
+    }
+}
+
+pub(super) mod systems {
+    use super::*;
+    use components::*;
+    use events::*;
+
+    pub fn solve_constraints(
+        mut commands: Commands,
+        constraints: Query<(Entity, &Constraint)>,
+        mut bodies: Query<(&mut Position, Option<&mut Velocity>, Option<&Mass>)>,
+        mut events: EventWriter<ConstraintBroken>,
+        time: Res<Time>,
+    ) {
+        let dt = time.delta_seconds();
+        if dt <= 0. {
+            return;
+        }
+
+        for (entity, constraint) in constraints.iter() {
+            if bodies.get(constraint.other).is_err() {
+                commands.entity(entity).remove::<Constraint>();
+                continue;
+            }
+            let Ok([(mut pos_a, mut vel_a, mass_a), (mut pos_b, mut vel_b, mass_b)]) =
+                bodies.get_many_mut([entity, constraint.other])
+            else {
+                continue;
+            };
+
+            // Inverse masses, zero for fixed anchors.
+            let w_a = vel_a
+                .as_ref()
+                .map_or(0., |_| 1. / Mass::or_default(mass_a).0);
+            let w_b = vel_b
+                .as_ref()
+                .map_or(0., |_| 1. / Mass::or_default(mass_b).0);
+            let w_sum = w_a + w_b;
+
+            let delta = pos_b.0 - pos_a.0;
+            let dist = delta.length();
+            if w_sum <= 0. || dist <= f32::EPSILON {
+                continue;
+            }
+            let n = delta / dist;
+            // Speed at which the two entities move apart.
+            let separating = vel_b.as_ref().map_or(Vec2::ZERO, |v| v.0).dot(n)
+                - vel_a.as_ref().map_or(Vec2::ZERO, |v| v.0).dot(n);
+
+            // Impulse pulling the entities together, and position correction to apply.
+            let (impulse, correction) = match constraint.kind {
+                ConstraintKind::Spring {
+                    rest_length,
+                    stiffness,
+                    damping,
+                } => {
+                    let force = stiffness * (dist - rest_length) + damping * separating;
+                    (force * dt, 0.)
+                }
+                ConstraintKind::Rope { max_length } => {
+                    if dist <= max_length {
+                        continue;
+                    }
+                    (separating.max(0.) / w_sum, dist - max_length)
+                }
+                ConstraintKind::Distance { length } => (separating / w_sum, dist - length),
+            };
+            // Force needed to resolve the correction within a frame.
+            let force = (impulse + correction / (dt * w_sum)).abs() / dt;
+
+            if constraint.break_force.is_some_and(|max| force > max) {
+                commands.entity(entity).remove::<Constraint>();
+                events.send(ConstraintBroken {
+                    entity,
+                    other: constraint.other,
+                    kind: constraint.kind,
+                    force,
+                });
+                continue;
+            }
+
+            pos_a.0 += n * correction * w_a / w_sum;
+            pos_b.0 -= n * correction * w_b / w_sum;
+            if let Some(vel_a) = vel_a.as_mut() {
+                vel_a.0 += n * impulse * w_a;
+            }
+            if let Some(vel_b) = vel_b.as_mut() {
+                vel_b.0 -= n * impulse * w_b;
+            }
+        }
+    }
+}
+
+pub mod prelude {
+    pub use super::components::*;
+    pub use super::events::*;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    use components::*;
+    use events::*;
+
+    /// A fixed anchor at the origin, and a body linked to it.
+    fn anchored(
+        pos: Vec2,
+        vel: Vec2,
+        constraint: impl Fn(Entity) -> Constraint,
+    ) -> (World, Entity) {
+        let mut world = World::new();
+        let mut time = Time::<()>::default();
+        time.advance_by(Duration::from_millis(100));
+        world.insert_resource(time);
+        world.init_resource::<Events<ConstraintBroken>>();
+
+        let anchor = world.spawn(Position(Vec2::ZERO)).id();
+        let body = world
+            .spawn((Position(pos), Velocity(vel), constraint(anchor)))
+            .id();
+        world.run_system_once(systems::solve_constraints);
+        (world, body)
+    }
+
+    fn broken(world: &mut World) -> Vec<(Entity, f32)> {
+        world
+            .resource_mut::<Events<ConstraintBroken>>()
+            .drain()
+            .map(|event| (event.entity, event.force))
+            .collect()
+    }
+
+    #[test]
+    fn rope_slack() {
+        let (world, body) = anchored(Vec2::new(5., 0.), Vec2::X, |anchor| {
+            Constraint::rope(anchor, 10.)
+        });
+
+        assert_eq!(world.get::<Position>(body).unwrap().0, Vec2::new(5., 0.));
+        assert_eq!(world.get::<Velocity>(body).unwrap().0, Vec2::X);
+    }
+
+    #[test]
+    fn rope_taut() {
+        let (world, body) = anchored(Vec2::new(15., 0.), Vec2::new(3., 4.), |anchor| {
+            Constraint::rope(anchor, 10.)
+        });
+
+        // Pulled back to its length, keeping only the sideways velocity.
+        assert_eq!(world.get::<Position>(body).unwrap().0, Vec2::new(10., 0.));
+        assert_eq!(world.get::<Velocity>(body).unwrap().0, Vec2::new(0., 4.));
+    }
+
+    #[test]
+    fn breaks_over_threshold() {
+        // Resolving 10 units within a frame of 0.1s takes a force of 1000.
+        let (mut world, body) = anchored(Vec2::new(20., 0.), Vec2::ZERO, |anchor| {
+            Constraint::distance(anchor, 10.).with_break_force(500.)
+        });
+
+        assert!(world.get::<Constraint>(body).is_none());
+        assert_eq!(world.get::<Position>(body).unwrap().0, Vec2::new(20., 0.));
+        let events = broken(&mut world);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].0, body);
+        assert!((events[0].1 - 1000.).abs() < 1e-2);
+    }
+
+    #[test]
+    fn holds_under_threshold() {
+        let (mut world, body) = anchored(Vec2::new(20., 0.), Vec2::ZERO, |anchor| {
+            Constraint::distance(anchor, 10.).with_break_force(2000.)
+        });
+
+        assert!(world.get::<Constraint>(body).is_some());
+        assert_eq!(world.get::<Position>(body).unwrap().0, Vec2::new(10., 0.));
+        assert!(broken(&mut world).is_empty());
+    }
+}
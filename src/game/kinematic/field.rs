@@ -0,0 +1,291 @@
+//! Force fields: attractors, repulsors and directional winds.
+
+use bevy::prelude::*;
+
+use super::super::allegience::prelude::*;
+use super::components::*;
+
+pub mod components {
+    use super::*;
+
+    /// How the strength of a [`ForceField`] decreases with distance.
+    #[derive(Debug, Clone, Copy, PartialEq, Default)]
+    pub enum Falloff {
+        /// Full strength within the whole range.
+        #[default]
+        Constant,
+        /// Linearly decreases to zero at the edge of the range.
+        Linear,
+        /// Smoothly decreases to zero at the edge of the range.
+        Smooth,
+        /// Full strength up to `min_distance`, then decreases with the square of the distance.
+        /// A `min_distance` below one unit is treated as one, not to vanish everywhere else.
+        InverseSquare { min_distance: f32 },
+    }
+    impl Falloff {
+        /// Zero for an empty range.
+        pub fn factor(self, distance: f32, range: f32) -> f32 {
+            if range <= 0. {
+                return 0.;
+            }
+            let t = (1. - distance / range).clamp(0., 1.);
+            match self {
+                Falloff::Constant => 1.,
+                Falloff::Linear => t,
+                Falloff::Smooth => t * t * (3. - 2. * t),
+                Falloff::InverseSquare { min_distance } => {
+                    let min_distance = min_distance.max(1.);
+                    (min_distance / distance.max(min_distance)).powi(2)
+                }
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum FieldKind {
+        /// Pull bodies towards the source.
+        Attractor,
+        /// Push bodies away from the source.
+        Repulsor,
+        /// Push bodies in a fixed direction.
+        Directional(Vec2),
+    }
+
+    /// A source of force applied to every kinematic body within range.
+    ///
+    /// Prerequisite: [`Position`]
+    #[derive(Debug, Clone, Copy, PartialEq, Component)]
+    pub struct ForceField {
+        pub kind: FieldKind,
+        /// Force at full strength.
+        pub strength: f32,
+        pub range: f32,
+        pub falloff: Falloff,
+        /// Factions whose bodies are affected.
+        pub affects: Factions,
+        /// Whether bodies without a faction are affected.
+        pub affects_unaligned: bool,
+    }
+    impl ForceField {
+        pub fn new(kind: FieldKind, strength: f32, range: f32) -> Self {
+            Self {
+                kind,
+                strength,
+                range,
+                falloff: Falloff::default(),
+                affects: Factions::ALL,
+                affects_unaligned: true,
+            }
+        }
+        pub fn attractor(strength: f32, range: f32) -> Self {
+            Self::new(FieldKind::Attractor, strength, range)
+        }
+        pub fn repulsor(strength: f32, range: f32) -> Self {
+            Self::new(FieldKind::Repulsor, strength, range)
+        }
+        pub fn directional(dir: Vec2, strength: f32, range: f32) -> Self {
+            Self::new(FieldKind::Directional(dir), strength, range)
+        }
+
+        pub fn with_falloff(mut self, falloff: Falloff) -> Self {
+            self.falloff = falloff;
+            self
+        }
+        pub fn affecting(mut self, affects: Factions, affects_unaligned: bool) -> Self {
+            self.affects = affects;
+            self.affects_unaligned = affects_unaligned;
+            self
+        }
+
+        pub fn affects(&self, faction: Option<Faction>) -> bool {
+            faction.map_or(self.affects_unaligned, |faction| {
+                self.affects.contains(faction.flag())
+            })
+        }
+
+        /// The force applied to a body at `pos` by a field at `source`.
+        pub fn force(&self, source: Vec2, pos: Vec2) -> Vec2 {
+            let offset = pos - source;
+            let distance = offset.length();
+            if distance > self.range {
+                return Vec2::ZERO;
+            }
+
+            let dir = match self.kind {
+                FieldKind::Attractor => -offset.normalize_or_zero(),
+                FieldKind::Repulsor => offset.normalize_or_zero(),
+                FieldKind::Directional(dir) => dir.normalize_or_zero(),
+            };
+            dir * self.strength * self.falloff.factor(distance, self.range)
+        }
+    }
+
+    /// Despawn a field source once the timer finishes.
+    #[derive(Debug, Clone, Component)]
+    pub struct FieldLifetime(pub Timer);
+    impl FieldLifetime {
+        pub fn new(duration: std::time::Duration) -> Self {
+            Self(Timer::new(duration, TimerMode::Once))
+        }
+    }
+}
+
+pub(super) mod systems {
+    use super::*;
+    use components::*;
+
+    #[allow(clippy::type_complexity)]
+    pub fn apply_force_fields(
+        mut bodies: Query<(
+            Entity,
+            &Position,
+            &mut Acceleration,
+            Option<&Mass>,
+            Option<&Faction>,
+        )>,
+        fields: Query<(Entity, &Position, &ForceField)>,
+    ) {
+        if fields.is_empty() {
+            return;
+        }
+        for (entity, pos, mut acc, mass, faction) in bodies.iter_mut() {
+            let force: Vec2 = fields
+                .iter()
+                .filter(|&(source, _, field)| source != entity && field.affects(faction.copied()))
+                .map(|(_, source_pos, field)| field.force(source_pos.0, pos.0))
+                .sum();
+
+            if force != Vec2::ZERO {
+                acc.accumulate(Mass::or_default(mass).acceleration(force));
+            }
+        }
+    }
+
+    pub fn tick_down_field_lifetime(
+        mut commands: Commands,
+        mut query: Query<(Entity, &mut FieldLifetime)>,
+        time: Res<Time>,
+    ) {
+        for (entity, mut lifetime) in query.iter_mut() {
+            if lifetime.0.tick(time.delta()).finished() {
+                commands.entity(entity).despawn();
+            }
+        }
+    }
+}
+
+pub mod prelude {
+    pub use super::components::*;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    use components::*;
+
+    const FALLOFFS: [Falloff; 4] = [
+        Falloff::Constant,
+        Falloff::Linear,
+        Falloff::Smooth,
+        Falloff::InverseSquare { min_distance: 2. },
+    ];
+
+    #[test]
+    fn falloff_within_range() {
+        for falloff in FALLOFFS {
+            assert_eq!(falloff.factor(0., 10.), 1., "{falloff:?}");
+        }
+        assert_eq!(Falloff::Linear.factor(5., 10.), 0.5);
+        assert_eq!(Falloff::Smooth.factor(5., 10.), 0.5);
+        assert_eq!(Falloff::Linear.factor(20., 10.), 0.);
+        let inverse_square = Falloff::InverseSquare { min_distance: 2. };
+        assert_eq!(inverse_square.factor(1., 10.), 1.);
+        assert_eq!(inverse_square.factor(4., 10.), 0.25);
+    }
+
+    #[test]
+    fn falloff_edge_cases() {
+        // An empty range affects nothing.
+        for falloff in FALLOFFS {
+            assert_eq!(falloff.factor(0., 0.), 0., "{falloff:?}");
+            assert_eq!(falloff.factor(1., -1.), 0., "{falloff:?}");
+        }
+        // No minimum distance: the same as one unit.
+        let inverse_square = Falloff::InverseSquare { min_distance: 0. };
+        assert_eq!(inverse_square.factor(0., 10.), 1.);
+        assert_eq!(inverse_square.factor(1., 10.), 1.);
+        assert_eq!(inverse_square.factor(2., 10.), 0.25);
+    }
+
+    fn body(world: &mut World, pos: Vec2) -> Entity {
+        world.spawn((Position(pos), Acceleration::default())).id()
+    }
+
+    fn acc(world: &World, entity: Entity) -> Vec2 {
+        world.get::<Acceleration>(entity).unwrap().0
+    }
+
+    #[test]
+    fn fields_push_affected_bodies() {
+        let mut world = World::new();
+        let field = world
+            .spawn((
+                Position(Vec2::ZERO),
+                Acceleration::default(),
+                ForceField::repulsor(8., 100.).affecting(Factions::A, false),
+            ))
+            .id();
+        let light = body(&mut world, Vec2::X * 10.);
+        world.entity_mut(light).insert(Faction::A);
+        let heavy = body(&mut world, Vec2::Y * 10.);
+        world.entity_mut(heavy).insert((Faction::A, Mass(4.)));
+        let other = body(&mut world, Vec2::X * 10.);
+        world.entity_mut(other).insert(Faction::B);
+        let unaligned = body(&mut world, Vec2::X * 10.);
+        let far = body(&mut world, Vec2::X * 200.);
+        world.entity_mut(far).insert(Faction::A);
+        world.run_system_once(systems::apply_force_fields);
+
+        assert_eq!(acc(&world, light), Vec2::X * 8.);
+        assert_eq!(acc(&world, heavy), Vec2::Y * 2.);
+        assert_eq!(acc(&world, other), Vec2::ZERO);
+        assert_eq!(acc(&world, unaligned), Vec2::ZERO);
+        assert_eq!(acc(&world, far), Vec2::ZERO);
+
+        *world.get_mut::<ForceField>(field).unwrap() = ForceField::directional(Vec2::Y, 8., 100.);
+        world.run_system_once(systems::apply_force_fields);
+        assert_eq!(acc(&world, unaligned), Vec2::Y * 8.);
+        // Not pushed by itself.
+        assert_eq!(acc(&world, field), Vec2::ZERO);
+    }
+
+    #[test]
+    fn fields_expire() {
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        let field = world
+            .spawn((
+                Position(Vec2::ZERO),
+                ForceField::attractor(1., 10.),
+                FieldLifetime::new(Duration::from_secs(2)),
+            ))
+            .id();
+
+        world
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs(1));
+        world.run_system_once(systems::tick_down_field_lifetime);
+        assert!(world.get_entity(field).is_some());
+
+        world
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs(1));
+        world.run_system_once(systems::tick_down_field_lifetime);
+        assert!(world.get_entity(field).is_none());
+    }
+}
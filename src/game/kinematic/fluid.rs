@@ -0,0 +1,208 @@
+//! Regions with their own fluid density and flow, e.g. water, mud or wind tunnels.
+
+use bevy::prelude::*;
+
+use super::components::*;
+
+pub mod components {
+    use super::*;
+
+    /// The shape of a region, centered on its [`Position`].
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum ZoneShape {
+        Circle { radius: f32 },
+        Rect { half_size: Vec2 },
+    }
+    impl ZoneShape {
+        pub fn contains(&self, center: Vec2, point: Vec2) -> bool {
+            let offset = point - center;
+            match *self {
+                ZoneShape::Circle { radius } => offset.length_squared() <= radius * radius,
+                ZoneShape::Rect { half_size } => {
+                    offset.x.abs() <= half_size.x && offset.y.abs() <= half_size.y
+                }
+            }
+        }
+    }
+
+    /// A region that overrides the global `FluidDensity` and moves the fluid inside it.
+    ///
+    /// Prerequisite: [`Position`]
+    #[derive(Debug, Clone, Copy, PartialEq, Component)]
+    pub struct FluidZone {
+        pub shape: ZoneShape,
+        pub density: f32,
+        /// Velocity of the fluid, e.g. a current or a conveyor belt.
+        pub flow: Vec2,
+        /// Used by [`FluidBlending::Priority`](super::resources::FluidBlending::Priority).
+        pub priority: i32,
+    }
+    impl FluidZone {
+        pub fn new(shape: ZoneShape, density: f32) -> Self {
+            Self {
+                shape,
+                density,
+                flow: Vec2::ZERO,
+                priority: 0,
+            }
+        }
+        pub fn with_flow(mut self, flow: Vec2) -> Self {
+            self.flow = flow;
+            self
+        }
+        pub fn with_priority(mut self, priority: i32) -> Self {
+            self.priority = priority;
+            self
+        }
+    }
+}
+
+pub mod resources {
+    use super::*;
+    use components::*;
+
+    /// The fluid at a point.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct Fluid {
+        pub density: f32,
+        pub flow: Vec2,
+    }
+
+    /// How overlapping [`FluidZone`]s are combined.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Resource)]
+    pub enum FluidBlending {
+        /// The zone with the highest priority wins.
+        /// Ties go to the densest zone.
+        #[default]
+        Priority,
+        /// Densities are averaged, flows are averaged weighted by density.
+        Average,
+    }
+    impl FluidBlending {
+        /// Sample the fluid at `point`, falling back to the `ambient` density outside of every zone.
+        pub fn sample<'a>(
+            self,
+            point: Vec2,
+            zones: impl IntoIterator<Item = (&'a Position, &'a FluidZone)>,
+            ambient: f32,
+        ) -> Fluid {
+            let containing = zones
+                .into_iter()
+                .filter(|(pos, zone)| zone.shape.contains(pos.0, point))
+                .map(|(_, zone)| zone);
+
+            match self {
+                FluidBlending::Priority => containing
+                    .max_by(|a, b| {
+                        a.priority
+                            .cmp(&b.priority)
+                            .then(a.density.total_cmp(&b.density))
+                    })
+                    .map_or(
+                        Fluid {
+                            density: ambient,
+                            flow: Vec2::ZERO,
+                        },
+                        |zone| Fluid {
+                            density: zone.density,
+                            flow: zone.flow,
+                        },
+                    ),
+                FluidBlending::Average => {
+                    let (count, density, flow) =
+                        containing.fold((0, 0., Vec2::ZERO), |(count, density, flow), zone| {
+                            (
+                                count + 1,
+                                density + zone.density,
+                                flow + zone.flow * zone.density,
+                            )
+                        });
+                    if count == 0 {
+                        Fluid {
+                            density: ambient,
+                            flow: Vec2::ZERO,
+                        }
+                    } else {
+                        Fluid {
+                            density: density / count as f32,
+                            flow: if density > 0. {
+                                flow / density
+                            } else {
+                                Vec2::ZERO
+                            },
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub mod prelude {
+    pub use super::components::*;
+    pub use super::resources::*;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use components::*;
+    use resources::*;
+
+    fn zones() -> [(Position, FluidZone); 2] {
+        [
+            (
+                Position(Vec2::ZERO),
+                FluidZone::new(ZoneShape::Circle { radius: 10. }, 1.).with_flow(Vec2::X),
+            ),
+            (
+                Position(Vec2::new(5., 0.)),
+                FluidZone::new(
+                    ZoneShape::Rect {
+                        half_size: Vec2::splat(5.),
+                    },
+                    3.,
+                )
+                .with_flow(Vec2::Y)
+                .with_priority(-1),
+            ),
+        ]
+    }
+
+    #[test]
+    fn outside_every_zone() {
+        let zones = zones();
+        let fluid = FluidBlending::Priority.sample(
+            Vec2::new(0., 20.),
+            zones.iter().map(|(p, z)| (p, z)),
+            0.5,
+        );
+
+        assert_eq!(fluid.density, 0.5);
+        assert_eq!(fluid.flow, Vec2::ZERO);
+    }
+
+    #[test]
+    fn priority_blending() {
+        let zones = zones();
+        let fluid = FluidBlending::Priority.sample(
+            Vec2::new(5., 0.),
+            zones.iter().map(|(p, z)| (p, z)),
+            0.,
+        );
+
+        assert_eq!(fluid.density, 1.);
+        assert_eq!(fluid.flow, Vec2::X);
+    }
+
+    #[test]
+    fn average_blending() {
+        let zones = zones();
+        let fluid =
+            FluidBlending::Average.sample(Vec2::new(5., 0.), zones.iter().map(|(p, z)| (p, z)), 0.);
+
+        assert_eq!(fluid.density, 2.);
+        assert_eq!(fluid.flow, (Vec2::X + 3. * Vec2::Y) / 4.);
+    }
+}
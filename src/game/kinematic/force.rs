@@ -0,0 +1,329 @@
+//! Mass-aware forces and impulses.
+
+use std::f32::consts::{PI, TAU};
+
+use bevy::{ecs::system::EntityCommands, prelude::*};
+
+use super::components::*;
+
+/// Wrap an angle into `[-PI, PI)`.
+pub fn wrap_angle(angle: f32) -> f32 {
+    (angle + PI).rem_euclid(TAU) - PI
+}
+
+pub mod components {
+    use super::*;
+
+    /// Force accumulator, converted into [`Acceleration`] through [`Mass`].
+    /// Cleared after being applied, unless it is persistent.
+    ///
+    /// Prerequisite: [`Acceleration`]
+    #[derive(Debug, Default, Component)]
+    pub struct ExternalForce {
+        pub force: Vec2,
+        /// Torque around the center of mass, turning entities with an [`AngularVelocity`].
+        pub torque: f32,
+        /// Keep applying the force every frame instead of clearing it.
+        pub persistent: bool,
+    }
+    impl ExternalForce {
+        pub fn new(force: Vec2) -> Self {
+            Self {
+                force,
+                ..Default::default()
+            }
+        }
+        pub fn persistent(force: Vec2) -> Self {
+            Self {
+                force,
+                persistent: true,
+                ..Default::default()
+            }
+        }
+
+        pub fn apply_force(&mut self, force: Vec2) -> &mut Self {
+            self.force += force;
+            self
+        }
+        /// Apply a force at a world `point` of a body whose center of mass is at `center`.
+        pub fn apply_force_at_point(
+            &mut self,
+            force: Vec2,
+            point: Vec2,
+            center: Vec2,
+        ) -> &mut Self {
+            self.force += force;
+            self.torque += (point - center).perp_dot(force);
+            self
+        }
+        pub fn clear(&mut self) {
+            self.force = Vec2::ZERO;
+            self.torque = 0.;
+        }
+    }
+
+    /// Turning speed of the [`Heading`], in radians per second counterclockwise.
+    ///
+    /// Prerequisite: [`Heading`]
+    #[derive(Debug, Clone, Copy, PartialEq, Default, Component)]
+    pub struct AngularVelocity(pub f32);
+
+    /// Resistance to torque, as [`Mass`] is to force.
+    #[derive(Debug, Clone, Copy, PartialEq, Component)]
+    pub struct MomentOfInertia(pub f32);
+    impl Default for MomentOfInertia {
+        fn default() -> Self {
+            Self(1.)
+        }
+    }
+    impl MomentOfInertia {
+        /// The moment of inertia of entities without a [`MomentOfInertia`] component.
+        pub fn or_default(inertia: Option<&MomentOfInertia>) -> &MomentOfInertia {
+            inertia.unwrap_or(&MomentOfInertia(1.))
+        }
+
+        /// Angular acceleration caused by a torque.
+        pub fn angular_acceleration(&self, torque: f32) -> f32 {
+            torque / self.0
+        }
+    }
+
+    /// Impulse accumulator, converted into an instant change of [`Velocity`] through [`Mass`].
+    /// Cleared after being applied.
+    ///
+    /// Prerequisite: [`Velocity`]
+    #[derive(Debug, Default, Component)]
+    pub struct ExternalImpulse(pub Vec2);
+    impl ExternalImpulse {
+        pub fn apply_impulse(&mut self, impulse: Vec2) -> &mut Self {
+            self.0 += impulse;
+            self
+        }
+        pub fn clear(&mut self) {
+            self.0 = Vec2::ZERO;
+        }
+    }
+}
+
+pub mod commands {
+    use super::*;
+    use components::*;
+
+    /// Apply forces and impulses to an entity without querying for it.
+    /// The accumulators are inserted if the entity does not have them yet,
+    /// but a warning is logged if it has nothing for them to act on.
+    pub trait ForceCommandsExt {
+        /// Apply a force for one frame.
+        fn apply_force(&mut self, force: Vec2) -> &mut Self;
+        /// Apply a force at a world `point` for one frame.
+        fn apply_force_at_point(&mut self, force: Vec2, point: Vec2) -> &mut Self;
+        /// Apply a one-shot impulse, e.g. knockback or an explosion.
+        fn apply_impulse(&mut self, impulse: Vec2) -> &mut Self;
+    }
+
+    /// Warn that what is applied to an entity without a `C` has no effect.
+    fn warn_without<C: Component>(entity: &EntityWorldMut, applied: &str) {
+        if !entity.contains::<C>() {
+            warn!(
+                "{applied} applied to {:?} is ignored without {}",
+                entity.id(),
+                std::any::type_name::<C>()
+            );
+        }
+    }
+
+    impl ForceCommandsExt for EntityCommands<'_> {
+        fn apply_force(&mut self, force: Vec2) -> &mut Self {
+            self.add(move |entity: Entity, world: &mut World| {
+                let Some(mut entity) = world.get_entity_mut(entity) else {
+                    return;
+                };
+                warn_without::<Acceleration>(&entity, "Force");
+                if let Some(mut external) = entity.get_mut::<ExternalForce>() {
+                    external.apply_force(force);
+                } else {
+                    entity.insert(ExternalForce::new(force));
+                }
+            })
+        }
+
+        fn apply_force_at_point(&mut self, force: Vec2, point: Vec2) -> &mut Self {
+            self.add(move |entity: Entity, world: &mut World| {
+                let Some(mut entity) = world.get_entity_mut(entity) else {
+                    return;
+                };
+                warn_without::<Acceleration>(&entity, "Force");
+                let center = entity.get::<Position>().map_or(point, |pos| pos.0);
+                if let Some(mut external) = entity.get_mut::<ExternalForce>() {
+                    external.apply_force_at_point(force, point, center);
+                } else {
+                    let mut external = ExternalForce::default();
+                    external.apply_force_at_point(force, point, center);
+                    entity.insert(external);
+                }
+            })
+        }
+
+        fn apply_impulse(&mut self, impulse: Vec2) -> &mut Self {
+            self.add(move |entity: Entity, world: &mut World| {
+                let Some(mut entity) = world.get_entity_mut(entity) else {
+                    return;
+                };
+                warn_without::<Velocity>(&entity, "Impulse");
+                if let Some(mut external) = entity.get_mut::<ExternalImpulse>() {
+                    external.apply_impulse(impulse);
+                } else {
+                    entity.insert(ExternalImpulse(impulse));
+                }
+            })
+        }
+    }
+}
+
+pub(super) mod systems {
+    use super::*;
+    use components::*;
+
+    #[allow(clippy::type_complexity)]
+    pub fn apply_external_forces(
+        mut query: Query<(
+            &mut ExternalForce,
+            &mut Acceleration,
+            Option<&Mass>,
+            Option<&mut AngularVelocity>,
+            Option<&MomentOfInertia>,
+        )>,
+        time: Res<Time>,
+    ) {
+        let dt = time.delta_seconds();
+        for (mut external, mut acc, mass, angular, inertia) in query.iter_mut() {
+            if external.force == Vec2::ZERO && external.torque == 0. {
+                continue;
+            }
+            acc.accumulate(Mass::or_default(mass).acceleration(external.force));
+            if let Some(mut angular) = angular {
+                angular.0 +=
+                    MomentOfInertia::or_default(inertia).angular_acceleration(external.torque) * dt;
+            }
+            if !external.persistent {
+                external.clear();
+            }
+        }
+    }
+
+    pub fn update_angular(mut query: Query<(&mut Heading, &AngularVelocity)>, time: Res<Time>) {
+        let dt = time.delta_seconds();
+        for (mut heading, angular) in query.iter_mut() {
+            if angular.0 != 0. {
+                heading.0 = wrap_angle(heading.0 + angular.0 * dt);
+            }
+        }
+    }
+
+    pub fn apply_external_impulses(
+        mut query: Query<(&mut ExternalImpulse, &mut Velocity, Option<&Mass>)>,
+    ) {
+        for (mut external, mut vel, mass) in query.iter_mut() {
+            if external.0 == Vec2::ZERO {
+                continue;
+            }
+            vel.0 += Mass::or_default(mass).velocity_change(external.0);
+            external.clear();
+        }
+    }
+}
+
+pub mod prelude {
+    pub use super::commands::*;
+    pub use super::components::*;
+
+    pub use super::wrap_angle;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    use commands::*;
+    use components::*;
+
+    fn world() -> World {
+        let mut world = World::new();
+        let mut time = Time::<()>::default();
+        time.advance_by(Duration::from_secs(1));
+        world.insert_resource(time);
+        world
+    }
+
+    #[test]
+    fn forces_accumulate_through_mass() {
+        let mut world = world();
+        let heavy = world
+            .spawn((
+                ExternalForce::new(Vec2::X),
+                Acceleration::default(),
+                Mass(4.),
+            ))
+            .id();
+        let steady = world
+            .spawn((ExternalForce::persistent(Vec2::Y), Acceleration::default()))
+            .id();
+        world.commands().entity(heavy).apply_force(Vec2::X * 3.);
+        world.flush();
+        world.run_system_once(systems::apply_external_forces);
+
+        assert_eq!(world.get::<Acceleration>(heavy).unwrap().0, Vec2::X);
+        assert_eq!(world.get::<Acceleration>(steady).unwrap().0, Vec2::Y);
+        // Cleared once applied, unless persistent.
+        assert_eq!(world.get::<ExternalForce>(heavy).unwrap().force, Vec2::ZERO);
+        assert_eq!(world.get::<ExternalForce>(steady).unwrap().force, Vec2::Y);
+    }
+
+    #[test]
+    fn impulses_accumulate_through_mass() {
+        let mut world = world();
+        let entity = world.spawn((Velocity(Vec2::X), Mass(2.))).id();
+        world.commands().entity(entity).apply_impulse(Vec2::Y);
+        world.commands().entity(entity).apply_impulse(Vec2::Y);
+        world.flush();
+        world.run_system_once(systems::apply_external_impulses);
+
+        assert_eq!(world.get::<Velocity>(entity).unwrap().0, Vec2::ONE);
+        assert_eq!(world.get::<ExternalImpulse>(entity).unwrap().0, Vec2::ZERO);
+
+        // Nothing left to apply the next frame.
+        world.run_system_once(systems::apply_external_impulses);
+        assert_eq!(world.get::<Velocity>(entity).unwrap().0, Vec2::ONE);
+    }
+
+    #[test]
+    fn force_at_point_turns() {
+        let mut world = world();
+        let entity = world
+            .spawn((
+                Position(Vec2::ZERO),
+                Acceleration::default(),
+                Heading(0.),
+                AngularVelocity::default(),
+                MomentOfInertia(2.),
+            ))
+            .id();
+        // Pushing the right side up turns counterclockwise.
+        world
+            .commands()
+            .entity(entity)
+            .apply_force_at_point(Vec2::Y, Vec2::X);
+        world.flush();
+        world.run_system_once(systems::apply_external_forces);
+        world.run_system_once(systems::update_angular);
+
+        assert_eq!(world.get::<Acceleration>(entity).unwrap().0, Vec2::Y);
+        assert_eq!(world.get::<AngularVelocity>(entity).unwrap().0, 0.5);
+        assert_eq!(world.get::<Heading>(entity).unwrap().0, 0.5);
+        assert_eq!(world.get::<ExternalForce>(entity).unwrap().torque, 0.);
+    }
+}
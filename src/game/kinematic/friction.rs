@@ -0,0 +1,185 @@
+//! Surface friction of ground entities, separate from fluid drag.
+
+use bevy::prelude::*;
+
+use super::components::*;
+use super::fluid::prelude::*;
+use super::height::prelude::*;
+
+pub mod components {
+    use super::*;
+
+    /// Coulomb friction against the ground:
+    /// a constant deceleration that brings an entity to a full stop,
+    /// pressed against the ground by the [`HeightGravity`].
+    /// Does not apply while [`Airborne`].
+    ///
+    /// Prerequisite: [`Dampening`]
+    #[derive(Debug, Clone, Copy, PartialEq, Component)]
+    pub struct Friction {
+        pub coeff: f32,
+    }
+    impl Default for Friction {
+        fn default() -> Self {
+            Self { coeff: 0.1 }
+        }
+    }
+
+    /// Properties of the ground.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct Surface {
+        /// Multiplier of [`Friction::coeff`] on this surface.
+        pub friction: f32,
+        /// Multiplier of the deceleration of [`Decelerating`] entities on this surface.
+        pub traction: f32,
+    }
+    impl Surface {
+        pub const GROUND: Self = Self {
+            friction: 1.,
+            traction: 1.,
+        };
+        pub const ICE: Self = Self {
+            friction: 0.05,
+            traction: 0.1,
+        };
+        pub const MUD: Self = Self {
+            friction: 4.,
+            traction: 0.5,
+        };
+    }
+    impl Default for Surface {
+        fn default() -> Self {
+            Self::GROUND
+        }
+    }
+
+    /// A region of terrain with its own [`Surface`].
+    /// Overlapping terrains are resolved by priority.
+    ///
+    /// Prerequisite: [`Position`]
+    #[derive(Debug, Clone, Copy, PartialEq, Component)]
+    pub struct Terrain {
+        pub shape: ZoneShape,
+        pub surface: Surface,
+        pub priority: i32,
+    }
+    impl Terrain {
+        pub fn new(shape: ZoneShape, surface: Surface) -> Self {
+            Self {
+                shape,
+                surface,
+                priority: 0,
+            }
+        }
+        pub fn with_priority(mut self, priority: i32) -> Self {
+            self.priority = priority;
+            self
+        }
+    }
+}
+
+pub mod resources {
+    use super::*;
+    use components::*;
+
+    /// The surface outside of every [`Terrain`].
+    #[derive(Debug, Clone, Copy, PartialEq, Default, Resource)]
+    pub struct DefaultSurface(pub Surface);
+    impl DefaultSurface {
+        /// Sample the surface at `point`.
+        pub fn sample<'a>(
+            &self,
+            point: Vec2,
+            terrains: impl IntoIterator<Item = (&'a Position, &'a Terrain)>,
+        ) -> Surface {
+            terrains
+                .into_iter()
+                .filter(|(pos, terrain)| terrain.shape.contains(pos.0, point))
+                .max_by_key(|(_, terrain)| terrain.priority)
+                .map_or(self.0, |(_, terrain)| terrain.surface)
+        }
+    }
+}
+
+pub(super) mod systems {
+    use super::*;
+    use components::*;
+    use resources::*;
+
+    pub fn update_friction(
+        mut query: Query<(&Position, &Friction, &mut Dampening), Without<Airborne>>,
+        terrains: Query<(&Position, &Terrain)>,
+        default_surface: Res<DefaultSurface>,
+        gravity: Res<HeightGravity>,
+    ) {
+        for (pos, friction, mut damp) in query.iter_mut() {
+            let surface = default_surface.sample(pos.0, terrains.iter());
+            damp.accumulate(friction.coeff * surface.friction * gravity.0);
+        }
+    }
+}
+
+pub mod prelude {
+    pub use super::components::*;
+    pub use super::resources::*;
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::super::systems::update_decelerating;
+    use super::*;
+
+    use components::*;
+    use resources::*;
+
+    /// Default surface, whether airborne, and the expected friction and traction.
+    const SURFACES: [(Surface, bool, f32, f32); 4] = [
+        (Surface::GROUND, false, 60., 100.),
+        (Surface::ICE, false, 3., 10.),
+        (Surface::MUD, false, 240., 50.),
+        (Surface::MUD, true, 0., 0.),
+    ];
+
+    #[test]
+    fn friction_and_traction_by_surface() {
+        for (surface, airborne, friction, traction) in SURFACES {
+            let mut world = World::new();
+            world.insert_resource(DefaultSurface(surface));
+            world.init_resource::<HeightGravity>();
+
+            let rubbing = world
+                .spawn((
+                    Position(Vec2::ZERO),
+                    Friction::default(),
+                    Dampening::default(),
+                ))
+                .id();
+            let braking = world
+                .spawn((
+                    Position(Vec2::ZERO),
+                    SelfMoving { accel: 100. },
+                    Decelerating,
+                    Dampening::default(),
+                ))
+                .id();
+            if airborne {
+                world.entity_mut(rubbing).insert(Airborne);
+                world.entity_mut(braking).insert(Airborne);
+            }
+            world.run_system_once(systems::update_friction);
+            world.run_system_once(update_decelerating);
+
+            let dampening = |entity| world.get::<Dampening>(entity).unwrap().max_acc;
+            assert!(
+                (dampening(rubbing) - friction).abs() < 1e-4,
+                "{surface:?} {airborne}"
+            );
+            assert!(
+                (dampening(braking) - traction).abs() < 1e-4,
+                "{surface:?} {airborne}"
+            );
+        }
+    }
+}
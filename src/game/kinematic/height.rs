@@ -0,0 +1,229 @@
+//! Pseudo-3D height above the ground, e.g. for grenades, jumping enemies and artillery.
+
+use bevy::prelude::*;
+
+/// Launch velocity to hit `target` from `origin` following an arc with the given apex height.
+/// Returns the horizontal and the vertical velocity,
+/// or `None` if the apex is below either end of the arc.
+pub fn ballistic_launch(
+    origin: Vec2,
+    start_height: f32,
+    target: Vec2,
+    target_height: f32,
+    apex: f32,
+    gravity: f32,
+) -> Option<(Vec2, f32)> {
+    if gravity <= 0. || apex < start_height || apex < target_height {
+        return None;
+    }
+
+    let vertical = (2. * gravity * (apex - start_height)).sqrt();
+    let rise = vertical / gravity;
+    let fall = (2. * (apex - target_height) / gravity).sqrt();
+    let flight = rise + fall;
+    if flight <= 0. {
+        return None;
+    }
+
+    Some(((target - origin) / flight, vertical))
+}
+
+pub mod components {
+    use super::*;
+
+    /// Height above the ground and its vertical velocity.
+    ///
+    /// Prerequisite: [`Position`](super::super::components::Position)
+    #[derive(Debug, Clone, Copy, PartialEq, Default, Component)]
+    pub struct Height {
+        pub value: f32,
+        pub velocity: f32,
+    }
+    impl Height {
+        pub fn launched(velocity: f32) -> Self {
+            Self {
+                value: 0.,
+                velocity,
+            }
+        }
+
+        pub fn is_grounded(&self) -> bool {
+            self.value <= 0.
+        }
+    }
+
+    /// Fraction of the vertical speed kept when hitting the ground.
+    ///
+    /// Prerequisite: [`Height`]
+    #[derive(Debug, Clone, Copy, PartialEq, Component)]
+    pub struct Bounciness(pub f32);
+
+    /// Tags an entity that is above the ground.
+    /// Airborne entities are left out of the spatial index, so they neither collide with
+    /// nor are hit by things on the ground, and take no damage from grounded attackers.
+    ///
+    /// Maintained from [`Height`].
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Component)]
+    pub struct Airborne;
+}
+
+pub mod resources {
+    use super::*;
+
+    /// Gravity pulling entities with a [`Height`](super::components::Height) back to the ground.
+    #[derive(Debug, Clone, Copy, PartialEq, Resource)]
+    pub struct HeightGravity(pub f32);
+    impl Default for HeightGravity {
+        fn default() -> Self {
+            Self(600.)
+        }
+    }
+}
+
+pub mod events {
+    use super::*;
+
+    /// An airborne entity hit the ground.
+    #[derive(Debug, Event)]
+    pub struct Landed {
+        pub entity: Entity,
+        /// Vertical speed at impact.
+        pub speed: f32,
+    }
+}
+
+pub(super) mod systems {
+    use super::*;
+    use components::*;
+    use events::*;
+    use resources::*;
+
+    pub fn update_height(
+        mut commands: Commands,
+        mut query: Query<(Entity, &mut Height, Option<&Bounciness>, Has<Airborne>)>,
+        mut events: EventWriter<Landed>,
+        gravity: Res<HeightGravity>,
+        time: Res<Time>,
+    ) {
+        let dt = time.delta_seconds();
+        for (entity, mut height, bounciness, airborne) in query.iter_mut() {
+            if height.is_grounded() && height.velocity <= 0. {
+                if airborne {
+                    commands.entity(entity).remove::<Airborne>();
+                }
+                continue;
+            }
+
+            let dv = -gravity.0 * dt;
+            height.value += dt * (height.velocity + 0.5 * dv);
+            height.velocity += dv;
+
+            if height.is_grounded() {
+                let speed = -height.velocity;
+                height.value = 0.;
+                height.velocity = bounciness.map_or(0., |b| b.0 * speed);
+                if height.velocity <= 0. {
+                    commands.entity(entity).remove::<Airborne>();
+                }
+                events.send(Landed { entity, speed });
+            } else if !airborne {
+                commands.entity(entity).insert(Airborne);
+            }
+        }
+    }
+}
+
+pub mod prelude {
+    pub use super::ballistic_launch;
+    pub use super::components::*;
+    pub use super::events::*;
+    pub use super::resources::*;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    use components::*;
+    use events::*;
+    use resources::*;
+
+    #[test]
+    fn ballistic_arc_hits_target() {
+        let (origin, target) = (Vec2::new(10., -5.), Vec2::new(-40., 30.));
+        let (start_height, target_height, apex, gravity) = (5., 2., 50., 600.);
+
+        let (horizontal, vertical) =
+            ballistic_launch(origin, start_height, target, target_height, apex, gravity).unwrap();
+
+        // Time at which the arc comes back down to the target height.
+        let flight = (vertical
+            + (vertical * vertical - 2. * gravity * (target_height - start_height)).sqrt())
+            / gravity;
+        assert!((origin + horizontal * flight).distance(target) < 1e-3);
+
+        let rise = vertical / gravity;
+        let top = start_height + vertical * rise - 0.5 * gravity * rise * rise;
+        assert!((top - apex).abs() < 1e-3);
+    }
+
+    #[test]
+    fn ballistic_apex_too_low() {
+        assert_eq!(
+            ballistic_launch(Vec2::ZERO, 10., Vec2::X, 0., 5., 600.),
+            None
+        );
+    }
+
+    #[test]
+    fn dropped_entities_land_and_bounce() {
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world.insert_resource(HeightGravity(8.));
+        world.init_resource::<Events<Landed>>();
+        let dropped = Height {
+            value: 10.,
+            velocity: 0.,
+        };
+        let heavy = world.spawn(dropped).id();
+        let bouncy = world.spawn((dropped, Bounciness(0.5))).id();
+
+        let mut frame = |world: &mut World| {
+            world
+                .resource_mut::<Time>()
+                .advance_by(Duration::from_secs(1));
+            world.run_system_once(systems::update_height);
+            world
+                .resource_mut::<Events<Landed>>()
+                .drain()
+                .map(|landed| (landed.entity, landed.speed))
+                .collect::<Vec<_>>()
+        };
+        let state = |world: &World, entity| {
+            let height = world.get::<Height>(entity).unwrap();
+            (
+                height.value,
+                height.velocity,
+                world.entity(entity).contains::<Airborne>(),
+            )
+        };
+
+        assert!(frame(&mut world).is_empty());
+        assert_eq!(state(&world, heavy), (6., -8., true));
+
+        let mut landed = frame(&mut world);
+        landed.sort_by_key(|&(entity, _)| entity);
+        assert_eq!(landed, vec![(heavy, 16.), (bouncy, 16.)]);
+        assert_eq!(state(&world, heavy), (0., 0., false));
+        // Bounces back up with half the speed, still airborne.
+        assert_eq!(state(&world, bouncy), (0., 8., true));
+
+        assert!(frame(&mut world).is_empty());
+        assert_eq!(state(&world, heavy), (0., 0., false));
+        assert_eq!(state(&world, bouncy), (4., 0., true));
+    }
+}
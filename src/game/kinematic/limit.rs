@@ -0,0 +1,194 @@
+//! Speed and acceleration limits.
+
+use bevy::prelude::*;
+
+use super::components::*;
+
+pub mod components {
+    use super::*;
+
+    /// Maximum speed an entity can reach by itself.
+    /// External forces and impulses can still push it past this limit.
+    ///
+    /// Prerequisite: [`Propulsion`]
+    #[derive(Debug, Clone, Copy, PartialEq, Component)]
+    pub struct MaxSpeed(pub f32);
+
+    /// Maximum self-acceleration, regardless of how many actions contribute to it.
+    ///
+    /// Prerequisite: [`Propulsion`]
+    #[derive(Debug, Clone, Copy, PartialEq, Component)]
+    pub struct MaxAcceleration(pub f32);
+
+    /// Absolute speed limit, which external forces cannot exceed either.
+    ///
+    /// Prerequisite: [`Velocity`]
+    #[derive(Debug, Clone, Copy, PartialEq, Component)]
+    pub struct TerminalVelocity(pub f32);
+
+    /// Determine how much faster an entity can move while [`Sprinting`].
+    ///
+    /// Prerequisite: [`MaxSpeed`]
+    #[derive(Debug, Clone, Copy, PartialEq, Component)]
+    pub struct Sprint {
+        /// Multiplier of [`MaxSpeed`].
+        pub multiplier: f32,
+    }
+    impl Default for Sprint {
+        fn default() -> Self {
+            Self { multiplier: 1.5 }
+        }
+    }
+
+    /// Action: sprint.
+    ///
+    /// Prerequisite: [`Sprint`]
+    #[derive(Debug, Default, Component)]
+    pub struct Sprinting;
+}
+
+pub(super) mod systems {
+    use super::*;
+    use components::*;
+
+    /// Limit self-propelled accelerations and add them to the accumulated acceleration.
+    #[allow(clippy::type_complexity)]
+    pub fn apply_propulsion(
+        mut query: Query<(
+            &mut Propulsion,
+            &mut Acceleration,
+            &Velocity,
+            Option<&MaxSpeed>,
+            Option<&MaxAcceleration>,
+            Option<&Sprint>,
+            Has<Sprinting>,
+            Has<Immobilized>,
+        )>,
+        time: Res<Time>,
+    ) {
+        let dt = time.delta_seconds();
+        for (mut propulsion, mut acc, vel, max_speed, max_acc, sprint, sprinting, immobilized) in
+            query.iter_mut()
+        {
+            let mut accel = propulsion.0;
+            propulsion.reset();
+            if immobilized {
+                continue;
+            }
+
+            if let Some(max_acc) = max_acc {
+                accel = accel.clamp_length_max(max_acc.0);
+            }
+            if let Some(max_speed) = max_speed.filter(|_| dt > 0.) {
+                let multiplier = sprint.filter(|_| sprinting).map_or(1., |s| s.multiplier);
+                // Entities pushed past the limit may keep, but not increase, their speed.
+                let limit = (max_speed.0 * multiplier).max(vel.0.length());
+                let next = (vel.0 + accel * dt).clamp_length_max(limit);
+                accel = (next - vel.0) / dt;
+            }
+
+            acc.accumulate(accel);
+        }
+    }
+
+    pub fn limit_terminal_velocity(mut query: Query<(&mut Velocity, &TerminalVelocity)>) {
+        for (mut vel, terminal) in query.iter_mut() {
+            vel.0 = vel.0.clamp_length_max(terminal.0);
+        }
+    }
+}
+
+pub mod prelude {
+    pub use super::components::*;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::super::force::{components::ExternalImpulse, systems::apply_external_impulses};
+    use super::*;
+
+    use components::*;
+
+    fn world() -> World {
+        let mut world = World::new();
+        let mut time = Time::<()>::default();
+        time.advance_by(Duration::from_secs(1));
+        world.insert_resource(time);
+        world
+    }
+
+    fn propelled(world: &mut World, propulsion: Vec2) -> Entity {
+        world
+            .spawn((
+                Velocity::default(),
+                Acceleration::default(),
+                Propulsion(propulsion),
+            ))
+            .id()
+    }
+
+    #[test]
+    fn propulsion_limited() {
+        let mut world = world();
+        let speed = propelled(&mut world, Vec2::X * 100.);
+        world.entity_mut(speed).insert(MaxSpeed(10.));
+        let acc = propelled(&mut world, Vec2::X * 100.);
+        world.entity_mut(acc).insert(MaxAcceleration(5.));
+        let sprint = propelled(&mut world, Vec2::X * 100.);
+        world
+            .entity_mut(sprint)
+            .insert((MaxSpeed(10.), Sprint { multiplier: 1.5 }, Sprinting));
+        let immobilized = propelled(&mut world, Vec2::X * 100.);
+        world.entity_mut(immobilized).insert(Immobilized);
+        world.run_system_once(systems::apply_propulsion);
+
+        assert_eq!(world.get::<Acceleration>(speed).unwrap().0, Vec2::X * 10.);
+        assert_eq!(world.get::<Acceleration>(acc).unwrap().0, Vec2::X * 5.);
+        assert_eq!(world.get::<Acceleration>(sprint).unwrap().0, Vec2::X * 15.);
+        assert_eq!(
+            world.get::<Acceleration>(immobilized).unwrap().0,
+            Vec2::ZERO
+        );
+        // Consumed either way.
+        assert_eq!(world.get::<Propulsion>(immobilized).unwrap().0, Vec2::ZERO);
+    }
+
+    #[test]
+    fn impulses_exceed_max_speed() {
+        let mut world = world();
+        let entity = propelled(&mut world, Vec2::X * 100.);
+        world
+            .entity_mut(entity)
+            .insert((MaxSpeed(10.), ExternalImpulse(Vec2::X * 30.)));
+        world.run_system_once(apply_external_impulses);
+        world.run_system_once(systems::apply_propulsion);
+
+        // Pushed past the limit, it may keep its speed but not add to it.
+        assert_eq!(world.get::<Velocity>(entity).unwrap().0, Vec2::X * 30.);
+        assert_eq!(world.get::<Acceleration>(entity).unwrap().0, Vec2::ZERO);
+
+        // Slowing down is still allowed.
+        world.get_mut::<Propulsion>(entity).unwrap().0 = Vec2::X * -5.;
+        world.run_system_once(systems::apply_propulsion);
+        assert_eq!(world.get::<Acceleration>(entity).unwrap().0, Vec2::X * -5.);
+    }
+
+    #[test]
+    fn terminal_velocity_clamps_any_speed() {
+        let mut world = world();
+        let entity = world
+            .spawn((Velocity(Vec2::new(30., 40.)), TerminalVelocity(10.)))
+            .id();
+        world.run_system_once(systems::limit_terminal_velocity);
+
+        assert!(world
+            .get::<Velocity>(entity)
+            .unwrap()
+            .0
+            .abs_diff_eq(Vec2::new(6., 8.), 1e-5));
+    }
+}
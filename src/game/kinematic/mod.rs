@@ -0,0 +1,767 @@
+use bevy::prelude::*;
+
+pub mod attachment;
+pub mod constraint;
+pub mod field;
+pub mod fluid;
+pub mod force;
+pub mod friction;
+pub mod height;
+pub mod limit;
+pub mod pursuit;
+pub mod vehicle;
+
+pub mod components {
+    use super::*;
+
+    /// A world position.
+    #[derive(Debug, Default, Component)]
+    pub struct Position(pub Vec2);
+
+    /// Prerequisite: [`Position`]
+    #[derive(Debug, Default, Component)]
+    pub struct Velocity(pub Vec2);
+
+    /// Facing, as an angle in radians counterclockwise from the x-axis.
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Component)]
+    pub struct Heading(pub f32);
+    impl Heading {
+        pub fn from_dir(dir: Vec2) -> Self {
+            Self(dir.y.atan2(dir.x))
+        }
+        pub fn dir(&self) -> Vec2 {
+            Vec2::from_angle(self.0)
+        }
+    }
+
+    /// Acceleration accumulator.
+    /// Should be applied at the end of every frame.
+    ///
+    /// Prerequisite: [`Velocity`]
+    #[derive(Debug, Default, Component)]
+    pub struct Acceleration(pub Vec2);
+    impl Acceleration {
+        pub fn accumulate(&mut self, acc: Vec2) {
+            self.0 += acc;
+        }
+        pub fn reset(&mut self) {
+            self.0 = Vec2::ZERO;
+        }
+    }
+
+    /// Self-propelled acceleration accumulator.
+    /// Limited and added to [`Acceleration`] every frame.
+    ///
+    /// Prerequisite: [`Acceleration`]
+    #[derive(Debug, Default, Component)]
+    pub struct Propulsion(pub Vec2);
+    impl Propulsion {
+        pub fn accumulate(&mut self, acc: Vec2) {
+            self.0 += acc;
+        }
+        pub fn reset(&mut self) {
+            self.0 = Vec2::ZERO;
+        }
+    }
+
+    /// An acceleration that can never increase an entity's absolute velocity.
+    /// Should be applied before any other accelerations at the end of every frame.
+    ///
+    /// Prerequisite: [`Velocity`]
+    #[derive(Debug, Default, Component)]
+    pub struct Dampening {
+        pub max_acc: f32,
+    }
+    impl Dampening {
+        pub fn accumulate(&mut self, acc: f32) {
+            self.max_acc += acc;
+        }
+        pub fn reset(&mut self) {
+            self.max_acc = 0.
+        }
+    }
+
+    #[derive(Debug, Component)]
+    pub struct Mass(pub f32);
+    impl Default for Mass {
+        fn default() -> Self {
+            Mass(1.)
+        }
+    }
+    impl Mass {
+        /// The mass of entities without a [`Mass`] component.
+        pub fn or_default(mass: Option<&Mass>) -> &Mass {
+            mass.unwrap_or(&Mass(1.))
+        }
+
+        /// Acceleration caused by a force.
+        pub fn acceleration(&self, force: Vec2) -> Vec2 {
+            force / self.0
+        }
+        /// Instant change of velocity caused by an impulse.
+        pub fn velocity_change(&self, impulse: Vec2) -> Vec2 {
+            impulse / self.0
+        }
+    }
+
+    /// Determine the parameters for a self-moving entity.
+    ///
+    /// Prerequisite: [`Propulsion`]
+    #[derive(Debug, Component)]
+    pub struct SelfMoving {
+        /// Maximum self-acceleration.
+        pub accel: f32,
+    }
+
+    /// Suspends self-propulsion and steering, e.g. while stunned or rooted.
+    /// Movement actions are kept, and resume once this is removed.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Component)]
+    pub struct Immobilized;
+
+    /// Action: decelerate to zero velocity.
+    ///
+    /// Prerequisite: [`SelfMoving`]
+    #[derive(Debug, Default, Component)]
+    pub struct Decelerating;
+
+    /// Action: move to a destination.
+    ///
+    /// Prerequisite: [`SelfMoving`]
+    #[derive(Debug, Default, Component)]
+    pub struct MovingTo {
+        pub dest: Vec2,
+    }
+
+    /// Brake to arrive at the [`MovingTo`] destination at rest, instead of running through it.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Component)]
+    pub struct Arrive;
+
+    /// How [`Following`] picks its destination.
+    #[derive(Debug, Clone, Copy, PartialEq, Default)]
+    pub enum Pursuit {
+        /// Head for the target's current position.
+        #[default]
+        Direct,
+        /// Head for where the target will be, based on its [`Velocity`].
+        Lead,
+        /// Proportional navigation: turn the velocity `gain` times as fast as
+        /// the line of sight to the target rotates, for a collision course.
+        /// Ignores the standoff and orbit, e.g. for missiles.
+        Proportional { gain: f32 },
+    }
+
+    /// What [`Following`] does once its target is despawned, or dies if it is a unit.
+    /// A `TargetLost` event is sent either way.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub enum OnTargetLost {
+        /// Stop following and decelerate.
+        #[default]
+        Stop,
+        /// Stop following, but still move to the last known position.
+        GoToLastKnown,
+    }
+
+    /// Circle around the target instead of approaching it.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct Orbit {
+        pub radius: f32,
+        pub clockwise: bool,
+    }
+
+    /// Action: follow a target.
+    ///
+    /// Prerequisite: [`MovingTo`]
+    #[derive(Debug, Clone, Copy, PartialEq, Component)]
+    pub struct Following {
+        pub target: Entity,
+        pub pursuit: Pursuit,
+        /// Distance to keep from the target.
+        pub standoff: f32,
+        pub orbit: Option<Orbit>,
+        pub on_lost: OnTargetLost,
+        /// Position the target was last seen at, kept up to date while it exists.
+        pub last_known: Option<Vec2>,
+    }
+    impl Following {
+        pub fn new(target: Entity) -> Self {
+            Self {
+                target,
+                pursuit: Pursuit::default(),
+                standoff: 0.,
+                orbit: None,
+                on_lost: OnTargetLost::default(),
+                last_known: None,
+            }
+        }
+        pub fn with_lead(mut self) -> Self {
+            self.pursuit = Pursuit::Lead;
+            self
+        }
+        pub fn with_standoff(mut self, standoff: f32) -> Self {
+            self.standoff = standoff;
+            self
+        }
+        pub fn with_orbit(mut self, radius: f32, clockwise: bool) -> Self {
+            self.orbit = Some(Orbit { radius, clockwise });
+            self
+        }
+        pub fn on_lost(mut self, on_lost: OnTargetLost) -> Self {
+            self.on_lost = on_lost;
+            self
+        }
+
+        /// Stop following as [`OnTargetLost`] says, and return where the target was last seen.
+        pub fn lose(
+            &self,
+            entity: &mut bevy::ecs::system::EntityCommands,
+            moving_to: &mut MovingTo,
+        ) -> Vec2 {
+            let last_known = self.last_known.unwrap_or(moving_to.dest);
+            entity.remove::<Following>();
+            match self.on_lost {
+                OnTargetLost::Stop => {
+                    entity.remove::<MovingTo>().insert(Decelerating);
+                }
+                OnTargetLost::GoToLastKnown => moving_to.dest = last_known,
+            }
+            last_known
+        }
+    }
+
+    /// Action: move in a direction.
+    #[derive(Debug, Default, Component)]
+    pub struct MovingIn {
+        pub dir: Vec2,
+    }
+
+    /// This component implies that the entity's cross-section size
+    /// is the same in all directions, i.e. it is symmetric.
+    #[derive(Debug, Component)]
+    pub struct CrossSectionSize(pub f32);
+    impl Default for CrossSectionSize {
+        fn default() -> Self {
+            Self(1.)
+        }
+    }
+
+    #[derive(Debug, Component)]
+    pub struct ExperienceDrag {
+        pub coeff: f32,
+    }
+    impl Default for ExperienceDrag {
+        fn default() -> Self {
+            Self { coeff: 1. }
+        }
+    }
+}
+
+pub mod bundles {
+    use super::*;
+    use components::*;
+
+    #[derive(Debug, Default, Bundle)]
+    pub struct FullKinematic {
+        pub position: Position,
+        pub velocity: Velocity,
+        pub acceleration: Acceleration,
+        pub propulsion: Propulsion,
+        pub dampening: Dampening,
+        pub mass: Mass,
+        pub experience_drag: ExperienceDrag,
+    }
+
+    #[derive(Debug, Default, Bundle)]
+    pub struct SymmeticFullKinematic {
+        pub position: Position,
+        pub velocity: Velocity,
+        pub acceleration: Acceleration,
+        pub propulsion: Propulsion,
+        pub dampening: Dampening,
+        pub mass: Mass,
+        pub cross_section_size: CrossSectionSize,
+        pub experience_drag: ExperienceDrag,
+    }
+}
+
+mod resources {
+    use super::*;
+
+    /// Density of the fluid outside of every fluid zone.
+    #[derive(Debug, Resource)]
+    pub struct FluidDensity(pub f32);
+}
+
+pub mod events {
+    use super::*;
+
+    /// The target of [`Following`](super::components::Following) was despawned, or died.
+    #[derive(Debug, Event)]
+    pub struct TargetLost {
+        pub entity: Entity,
+        pub target: Entity,
+        pub last_known: Vec2,
+    }
+}
+
+mod systems {
+    use super::*;
+    use components::*;
+    use events::*;
+    use fluid::prelude::*;
+    use friction::prelude::*;
+    use height::prelude::*;
+    use limit::prelude::*;
+    use pursuit::*;
+    use resources::*;
+    use vehicle::prelude::*;
+
+    /// Angle ahead of the follower on the orbit that it heads for.
+    const ORBIT_LEAD: f32 = std::f32::consts::FRAC_PI_4;
+
+    pub fn sync_pos_transform(mut query: Query<(&Position, &mut Transform)>) {
+        for (pos, mut transform) in query.iter_mut() {
+            transform.translation = pos.0.extend(transform.translation.z);
+        }
+    }
+
+    pub fn sync_heading_transform(mut query: Query<(&Heading, &mut Transform)>) {
+        for (heading, mut transform) in query.iter_mut() {
+            transform.rotation = Quat::from_rotation_z(heading.0);
+        }
+    }
+
+    /// Update position and velocity assuming constant acceleration.
+    pub fn update_kinematic(
+        mut query: Query<(
+            &mut Position,
+            &mut Velocity,
+            &mut Acceleration,
+            Option<&mut Dampening>,
+        )>,
+        time: Res<Time>,
+    ) {
+        let dt = time.delta_seconds();
+        for (mut pos, mut vel, mut acc, dampening) in query.iter_mut() {
+            let max_damp_acc = dampening.as_ref().map_or(0., |d| d.max_acc);
+            let max_damp_dv = max_damp_acc * dt;
+
+            let v = vel.0.length();
+            let damp_dv = max_damp_dv.min(v);
+
+            let dv = acc.0 * dt - vel.0.normalize_or_zero() * damp_dv;
+            // vel.0 * dt + 0.5 * dv * dt
+            let dp = dt * (vel.0 + 0.5 * dv);
+
+            pos.0 += dp;
+            vel.0 += dv;
+            acc.reset();
+            if let Some(mut dampening) = dampening {
+                dampening.reset();
+            }
+        }
+    }
+
+    /// Update position of non-accelerating entities.
+    pub fn update_movement(
+        mut query: Query<(&mut Position, &Velocity), Without<Acceleration>>,
+        time: Res<Time>,
+    ) {
+        let dt = time.delta_seconds();
+        for (mut pos, vel) in query.iter_mut() {
+            pos.0 += vel.0 * dt;
+        }
+    }
+
+    #[allow(clippy::type_complexity)]
+    pub fn update_moving_to_dest(
+        mut query: Query<
+            (
+                &Position,
+                &Velocity,
+                &mut Propulsion,
+                &SelfMoving,
+                Option<&MaxAcceleration>,
+                &MovingTo,
+                Has<Arrive>,
+            ),
+            Without<Vehicle>,
+        >,
+        time: Res<Time>,
+    ) {
+        let dt = time.delta_seconds();
+        for (pos, vel, mut propulsion, self_moving, max_acc, &MovingTo { dest }, arrive) in
+            query.iter_mut()
+        {
+            let offset = dest - pos.0;
+            if !arrive || dt <= 0. {
+                propulsion.accumulate(offset.normalize_or_zero() * self_moving.accel);
+                continue;
+            }
+
+            // Brake with the acceleration actually available.
+            let accel = max_acc.map_or(self_moving.accel, |max_acc| {
+                max_acc.0.min(self_moving.accel)
+            });
+            // Fastest speed from which the destination can be reached without overshooting.
+            let desired = offset.normalize_or_zero() * (2. * accel * offset.length()).sqrt();
+            let da = ((desired - vel.0) / dt).clamp_length_max(accel);
+
+            propulsion.accumulate(da);
+        }
+    }
+
+    #[allow(clippy::type_complexity)]
+    pub fn update_following(
+        mut commands: Commands,
+        mut query: Query<(
+            Entity,
+            &Position,
+            Option<&Velocity>,
+            &mut MovingTo,
+            &mut Following,
+            &SelfMoving,
+            Option<&MaxSpeed>,
+        )>,
+        targets: Query<(&Position, Option<&Velocity>)>,
+        mut events: EventWriter<TargetLost>,
+        time: Res<Time>,
+    ) {
+        let dt = time.delta_seconds();
+        for (entity, pos, vel, mut moving_to, mut following, self_moving, max_speed) in
+            query.iter_mut()
+        {
+            let Ok((target_pos, target_vel)) = targets.get(following.target) else {
+                let last_known = following.lose(&mut commands.entity(entity), &mut moving_to);
+                events.send(TargetLost {
+                    entity,
+                    target: following.target,
+                    last_known,
+                });
+                continue;
+            };
+            following.bypass_change_detection().last_known = Some(target_pos.0);
+
+            let target = match (following.pursuit, target_vel) {
+                (Pursuit::Proportional { gain }, _) => {
+                    let vel = vel.map_or(Vec2::ZERO, |vel| vel.0);
+                    let los = target_pos.0 - pos.0;
+                    let closing = target_vel.map_or(Vec2::ZERO, |vel| vel.0) - vel;
+                    let los_rate = los.perp_dot(closing) / los.length_squared().max(f32::EPSILON);
+                    // Turn towards the target directly until it's ahead.
+                    let dir = match vel.try_normalize() {
+                        Some(dir) if dir.dot(los) > 0. => {
+                            Vec2::from_angle(gain * los_rate * dt).rotate(dir)
+                        }
+                        _ => los.normalize_or_zero(),
+                    };
+                    moving_to.dest = pos.0 + dir * los.length();
+                    continue;
+                }
+                (Pursuit::Lead, Some(target_vel)) => intercept_point(
+                    pos.0,
+                    target_pos.0,
+                    target_vel.0,
+                    self_moving.accel,
+                    max_speed.map(|max_speed| max_speed.0),
+                ),
+                _ => target_pos.0,
+            };
+
+            let away = (pos.0 - target).normalize_or(Vec2::X);
+            moving_to.dest = match following.orbit {
+                Some(Orbit { radius, clockwise }) => {
+                    let lead = if clockwise { -ORBIT_LEAD } else { ORBIT_LEAD };
+                    target + Vec2::from_angle(lead).rotate(away) * radius
+                }
+                None if pos.0.distance(target) > following.standoff => {
+                    target + away * following.standoff
+                }
+                // Hold position within the standoff distance.
+                None => pos.0,
+            };
+        }
+    }
+
+    pub fn update_moving_in_dir(
+        mut query: Query<(&mut Propulsion, &SelfMoving, &MovingIn), Without<Vehicle>>,
+    ) {
+        for (mut propulsion, self_moving, &MovingIn { dir }) in query.iter_mut() {
+            propulsion.accumulate(dir * self_moving.accel);
+        }
+    }
+
+    /// Brake as hard as the surface's traction allows.
+    /// There is nothing to brake against while airborne.
+    #[allow(clippy::type_complexity)]
+    pub fn update_decelerating(
+        mut query: Query<
+            (&Position, &mut Dampening, &SelfMoving),
+            (With<Decelerating>, Without<Airborne>),
+        >,
+        terrains: Query<(&Position, &Terrain)>,
+        default_surface: Res<DefaultSurface>,
+    ) {
+        for (pos, mut damp, self_moving) in query.iter_mut() {
+            let surface = default_surface.sample(pos.0, terrains.iter());
+            damp.accumulate(self_moving.accel * surface.traction);
+        }
+    }
+
+    /// Apply drag against the fluid at each entity's position,
+    /// using the velocity relative to the fluid's flow.
+    #[allow(clippy::type_complexity)]
+    pub fn update_drag_symmetric(
+        mut query: Query<(
+            &Position,
+            &Velocity,
+            &Mass,
+            &mut Dampening,
+            &mut Acceleration,
+            &CrossSectionSize,
+            &ExperienceDrag,
+        )>,
+        zones: Query<(&Position, &FluidZone)>,
+        fluid_density: Res<FluidDensity>,
+        blending: Res<FluidBlending>,
+        time: Res<Time>,
+    ) {
+        let dt = time.delta_seconds();
+        // F_d = \frac{1}{2} \rho v^2 A C_d
+        for (pos, vel, mass, mut damp, mut acc, cross_section_size, drag) in query.iter_mut() {
+            let fluid = blending.sample(pos.0, zones.iter(), fluid_density.0);
+            let rel_vel = vel.0 - fluid.flow;
+
+            let force =
+                0.5 * fluid.density * rel_vel.length_squared() * cross_section_size.0 * drag.coeff;
+            let accel = force / mass.0;
+
+            if fluid.flow == Vec2::ZERO {
+                damp.accumulate(accel);
+            } else if dt > 0. {
+                // A flowing fluid can speed entities up, so this is not a dampening.
+                // It still must not push an entity past the flow within a single frame.
+                let dv = (accel * dt).min(rel_vel.length());
+                acc.accumulate(-rel_vel.normalize_or_zero() * dv / dt);
+            }
+        }
+    }
+
+    // TODO: Implement drag for asymmetric entities.
+}
+
+/// The phases of a kinematic frame, run in this order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, SystemSet)]
+pub enum KinematicSet {
+    /// Movement actions are turned into accelerations.
+    Steering,
+    /// External forces, drag and dampening are accumulated.
+    Forces,
+    /// Self-propelled accelerations are limited and accumulated.
+    Limits,
+    /// Accelerations are integrated into velocities and positions.
+    Integrate,
+    /// Constraints between entities are solved.
+    Constraints,
+    /// Transforms are synchronized with positions.
+    Sync,
+}
+
+pub struct KinematicPlugin;
+
+impl Plugin for KinematicPlugin {
+    fn build(&self, app: &mut App) {
+        use attachment::systems::*;
+        use constraint::prelude::*;
+        use constraint::systems::*;
+        use events::*;
+        use field::systems::*;
+        use fluid::prelude::*;
+        use force::systems::*;
+        use friction::prelude::*;
+        use friction::systems::*;
+        use height::prelude::*;
+        use height::systems::*;
+        use limit::systems::*;
+        use resources::*;
+        use systems::*;
+        use vehicle::systems::*;
+
+        app.insert_resource(FluidDensity(0.001))
+            .init_resource::<FluidBlending>()
+            .init_resource::<DefaultSurface>()
+            .add_event::<ConstraintBroken>()
+            .add_event::<TargetLost>()
+            .init_resource::<HeightGravity>()
+            .add_event::<Landed>()
+            .configure_sets(
+                Update,
+                (
+                    KinematicSet::Steering,
+                    KinematicSet::Forces,
+                    KinematicSet::Limits,
+                    KinematicSet::Integrate,
+                    KinematicSet::Constraints,
+                    KinematicSet::Sync,
+                )
+                    .chain(),
+            )
+            .add_systems(
+                Update,
+                (
+                    update_decelerating,
+                    update_moving_in_dir,
+                    (update_following, (update_moving_to_dest, steer_vehicles)).chain(),
+                )
+                    .in_set(KinematicSet::Steering),
+            )
+            .add_systems(
+                Update,
+                (
+                    update_drag_symmetric,
+                    update_friction,
+                    apply_lateral_grip,
+                    apply_force_fields,
+                    apply_external_forces,
+                    apply_external_impulses,
+                )
+                    .in_set(KinematicSet::Forces),
+            )
+            .add_systems(Update, apply_propulsion.in_set(KinematicSet::Limits))
+            .add_systems(
+                Update,
+                (
+                    (update_kinematic, update_movement, update_angular),
+                    limit_terminal_velocity,
+                    update_height,
+                )
+                    .chain()
+                    .in_set(KinematicSet::Integrate),
+            )
+            .add_systems(
+                Update,
+                (solve_constraints, resolve_attachments)
+                    .chain()
+                    .in_set(KinematicSet::Constraints),
+            )
+            .add_systems(
+                Update,
+                (sync_pos_transform, sync_heading_transform).in_set(KinematicSet::Sync),
+            )
+            .add_systems(Update, tick_down_field_lifetime);
+    }
+}
+
+pub mod prelude {
+    pub use super::attachment::prelude::*;
+    pub use super::bundles::*;
+    pub use super::components::*;
+    pub use super::constraint::prelude::*;
+    pub use super::events::*;
+    pub use super::field::prelude::*;
+    pub use super::fluid::prelude::*;
+    pub use super::force::prelude::*;
+    pub use super::friction::prelude::*;
+    pub use super::height::prelude::*;
+    pub use super::limit::prelude::*;
+    pub use super::pursuit::{intercept_point, time_to_reach};
+    pub use super::resources::*;
+    pub use super::vehicle::prelude::*;
+
+    pub use super::{KinematicPlugin, KinematicSet};
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    use components::*;
+    use events::*;
+
+    #[test]
+    fn target_lost_reports_the_last_seen_position() {
+        let mut world = World::new();
+        let mut time = Time::<()>::default();
+        time.advance_by(Duration::from_millis(16));
+        world.insert_resource(time);
+        world.init_resource::<Events<TargetLost>>();
+
+        let target = world.spawn(Position(Vec2::new(100., 0.))).id();
+        let follower = world
+            .spawn((
+                Position(Vec2::ZERO),
+                MovingTo { dest: Vec2::ZERO },
+                Following::new(target)
+                    .with_standoff(40.)
+                    .on_lost(OnTargetLost::GoToLastKnown),
+                SelfMoving { accel: 100. },
+            ))
+            .id();
+
+        world.run_system_once(systems::update_following);
+        assert_eq!(
+            world.get::<MovingTo>(follower).unwrap().dest,
+            Vec2::new(60., 0.)
+        );
+
+        world.entity_mut(target).despawn();
+        world.run_system_once(systems::update_following);
+
+        let lost: Vec<_> = world
+            .resource_mut::<Events<TargetLost>>()
+            .drain()
+            .map(|event| (event.entity, event.last_known))
+            .collect();
+        assert_eq!(lost, vec![(follower, Vec2::new(100., 0.))]);
+        assert!(world.get::<Following>(follower).is_none());
+        // Heads for where the target was, not for the standoff point.
+        assert_eq!(
+            world.get::<MovingTo>(follower).unwrap().dest,
+            Vec2::new(100., 0.)
+        );
+    }
+
+    #[test]
+    fn proportional_navigation_turns_with_the_line_of_sight() {
+        let mut world = World::new();
+        let mut time = Time::<()>::default();
+        time.advance_by(Duration::from_millis(100));
+        world.insert_resource(time);
+        world.init_resource::<Events<TargetLost>>();
+
+        let crossing = world
+            .spawn((Position(Vec2::new(100., 0.)), Velocity(Vec2::new(0., 50.))))
+            .id();
+        let behind = world.spawn(Position(Vec2::new(-100., 0.))).id();
+        let following = |target| Following {
+            pursuit: Pursuit::Proportional { gain: 4. },
+            ..Following::new(target)
+        };
+        let missiles: Vec<_> = [crossing, behind]
+            .into_iter()
+            .map(|target| {
+                world
+                    .spawn((
+                        Position(Vec2::ZERO),
+                        Velocity(Vec2::new(100., 0.)),
+                        MovingTo::default(),
+                        following(target),
+                        SelfMoving { accel: 100. },
+                    ))
+                    .id()
+            })
+            .collect();
+        world.run_system_once(systems::update_following);
+
+        // The line of sight turns at 0.5 rad/s, so the heading turns at 2 rad/s.
+        let dest = world.get::<MovingTo>(missiles[0]).unwrap().dest;
+        assert!((dest.to_angle() - 0.2).abs() < 1e-5);
+        assert!((dest.length() - 100.).abs() < 1e-3);
+        // Targets behind are turned towards directly.
+        let dest = world.get::<MovingTo>(missiles[1]).unwrap().dest;
+        assert!(dest.abs_diff_eq(Vec2::new(-100., 0.), 1e-3));
+    }
+}
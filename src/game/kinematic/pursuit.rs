@@ -0,0 +1,73 @@
+//! Lead pursuit of moving targets.
+
+use bevy::prelude::*;
+
+/// Refinements of the intercept estimate.
+const INTERCEPT_ITERATIONS: usize = 4;
+
+/// Time for a chaser starting from rest to cover `dist`,
+/// accelerating at `accel` up to `max_speed`.
+pub fn time_to_reach(dist: f32, accel: f32, max_speed: Option<f32>) -> f32 {
+    if accel <= 0. {
+        return 0.;
+    }
+
+    let t = (2. * dist / accel).sqrt();
+    match max_speed {
+        Some(max_speed) if max_speed > 0. && accel * t > max_speed => {
+            let t_accel = max_speed / accel;
+            let d_accel = 0.5 * max_speed * t_accel;
+            t_accel + (dist - d_accel) / max_speed
+        }
+        _ => t,
+    }
+}
+
+/// Estimate where a chaser at `pos` meets a target at `target_pos` moving with `target_vel`.
+pub fn intercept_point(
+    pos: Vec2,
+    target_pos: Vec2,
+    target_vel: Vec2,
+    accel: f32,
+    max_speed: Option<f32>,
+) -> Vec2 {
+    let mut point = target_pos;
+    for _ in 0..INTERCEPT_ITERATIONS {
+        let t = time_to_reach(pos.distance(point), accel, max_speed);
+        point = target_pos + target_vel * t;
+    }
+    point
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stationary_target() {
+        let point = intercept_point(Vec2::ZERO, Vec2::new(30., 40.), Vec2::ZERO, 100., None);
+
+        assert_eq!(point, Vec2::new(30., 40.));
+    }
+
+    #[test]
+    fn leads_moving_target() {
+        let (pos, target_pos, target_vel) = (Vec2::ZERO, Vec2::new(100., 0.), Vec2::new(0., 20.));
+        let (accel, max_speed) = (200., Some(50.));
+
+        let point = intercept_point(pos, target_pos, target_vel, accel, max_speed);
+
+        // The chaser and the target arrive at about the same time.
+        let chaser = time_to_reach(pos.distance(point), accel, max_speed);
+        let target = target_pos.distance(point) / target_vel.length();
+        assert!(point.y > 0.);
+        assert!((chaser - target).abs() < 0.05);
+    }
+
+    #[test]
+    fn speed_limited_travel_time() {
+        // 0.5 s to reach 50 px/s, covering 12.5 px, then 87.5 px at full speed.
+        assert!((time_to_reach(100., 100., Some(50.)) - 2.25).abs() < 1e-5);
+        assert!((time_to_reach(50., 100., None) - 1.).abs() < 1e-5);
+    }
+}
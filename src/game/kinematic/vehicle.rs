@@ -0,0 +1,242 @@
+//! Vehicles: entities that can only thrust along their heading, e.g. boats, cars or spaceships.
+
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+
+use super::components::*;
+use super::force::wrap_angle;
+
+/// Turn `angle` towards `target` by at most `max_step`, the shorter way around.
+pub fn turn_towards(angle: f32, target: f32, max_step: f32) -> f32 {
+    angle + wrap_angle(target - angle).clamp(-max_step, max_step)
+}
+
+pub mod components {
+    use super::*;
+
+    /// Movement mode that replaces the free movement of [`MovingIn`] and [`MovingTo`]
+    /// with thrust along the [`Heading`] and limited turning.
+    ///
+    /// Prerequisite: [`SelfMoving`], [`Heading`]
+    #[derive(Debug, Clone, Copy, PartialEq, Component)]
+    pub struct Vehicle {
+        /// Maximum turning speed, in radians per second.
+        pub max_yaw_rate: f32,
+        /// Minimum turning radius, limiting the turning speed at high speeds.
+        /// Zero allows turning on the spot.
+        pub turning_radius: f32,
+        /// Fraction of [`SelfMoving::accel`] available in reverse.
+        /// Zero disables reversing.
+        pub reverse_thrust: f32,
+        /// Deceleration of sideways velocity, e.g. tire grip or a keel.
+        /// Zero lets the vehicle drift freely.
+        pub lateral_grip: f32,
+        /// Distance from a [`MovingTo`] destination at which it is reached,
+        /// and the vehicle starts [`Decelerating`].
+        /// Vehicles brake to arrive there, unless [`Following`] a moving target.
+        pub arrival_radius: f32,
+    }
+    impl Vehicle {
+        /// Turning speed limit at a given speed.
+        pub fn yaw_rate(&self, speed: f32) -> f32 {
+            if self.turning_radius > 0. {
+                self.max_yaw_rate.min(speed / self.turning_radius)
+            } else {
+                self.max_yaw_rate
+            }
+        }
+
+        /// Whether `dest` cannot be reached by turning towards it,
+        /// because it lies within one of the turning circles.
+        pub fn within_turning_circle(&self, pos: Vec2, heading: Vec2, dest: Vec2) -> bool {
+            let side = heading.perp() * self.turning_radius;
+            let r2 = self.turning_radius * self.turning_radius;
+            dest.distance_squared(pos + side) < r2 || dest.distance_squared(pos - side) < r2
+        }
+    }
+}
+
+pub(super) mod systems {
+    use super::*;
+    use components::*;
+
+    #[allow(clippy::type_complexity)]
+    pub fn steer_vehicles(
+        mut commands: Commands,
+        mut query: Query<
+            (
+                Entity,
+                &Position,
+                &Velocity,
+                &mut Heading,
+                &mut Propulsion,
+                Option<&mut Dampening>,
+                &SelfMoving,
+                &Vehicle,
+                Option<&MovingTo>,
+                Option<&MovingIn>,
+                Has<Following>,
+            ),
+            Without<Immobilized>,
+        >,
+        time: Res<Time>,
+    ) {
+        let dt = time.delta_seconds();
+        for (
+            entity,
+            pos,
+            vel,
+            mut heading,
+            mut propulsion,
+            dampening,
+            self_moving,
+            vehicle,
+            moving_to,
+            moving_in,
+            following,
+        ) in query.iter_mut()
+        {
+            let desired = match (moving_in, moving_to) {
+                (Some(moving_in), _) if moving_in.dir != Vec2::ZERO => moving_in.dir,
+                (_, Some(moving_to)) => moving_to.dest - pos.0,
+                _ => continue,
+            };
+            let to_dest = moving_in.is_none() && moving_to.is_some();
+            let arriving = to_dest && !following;
+            if arriving && desired.length() <= vehicle.arrival_radius {
+                commands
+                    .entity(entity)
+                    .remove::<MovingTo>()
+                    .insert(Decelerating);
+                continue;
+            }
+            if desired.length_squared() <= f32::EPSILON {
+                continue;
+            }
+
+            let mut target = desired.y.atan2(desired.x);
+            let mut throttle = 1.;
+            if vehicle.reverse_thrust > 0. && wrap_angle(target - heading.0).abs() > PI / 2. {
+                target += PI;
+                throttle = -vehicle.reverse_thrust;
+            }
+
+            // A destination within a turning circle can only be reached
+            // by first driving straight to gain some distance.
+            let blocked =
+                to_dest && vehicle.within_turning_circle(pos.0, heading.dir(), pos.0 + desired);
+            let error = wrap_angle(target - heading.0);
+            if !blocked {
+                let yaw_rate = vehicle.yaw_rate(vel.0.length());
+                heading.0 = turn_towards(heading.0, target, yaw_rate * dt);
+                // Fast enough to turn at full rate, so ease off while misaligned.
+                // Slower vehicles keep driving along their turning circle instead.
+                if yaw_rate >= vehicle.max_yaw_rate {
+                    throttle *= error.cos().max(0.);
+                }
+            }
+
+            // Fastest speed from which the vehicle can still brake within the arrival radius.
+            let stopping = (desired.length() - vehicle.arrival_radius).max(0.);
+            if arriving && vel.0.length() > (2. * self_moving.accel * stopping).sqrt() {
+                throttle = 0.;
+                if let Some(mut dampening) = dampening {
+                    dampening.accumulate(self_moving.accel);
+                }
+            }
+
+            propulsion.accumulate(heading.dir() * throttle * self_moving.accel);
+        }
+    }
+
+    /// Decelerate the sideways velocity of vehicles.
+    pub fn apply_lateral_grip(
+        mut query: Query<(&Velocity, &Heading, &Vehicle, &mut Acceleration)>,
+        time: Res<Time>,
+    ) {
+        let dt = time.delta_seconds();
+        if dt <= 0. {
+            return;
+        }
+        for (vel, heading, vehicle, mut acc) in query.iter_mut() {
+            let dir = heading.dir();
+            let lateral = vel.0 - dir * vel.0.dot(dir);
+            let decel = vehicle.lateral_grip.min(lateral.length() / dt);
+            acc.accumulate(-lateral.normalize_or_zero() * decel);
+        }
+    }
+}
+
+pub mod prelude {
+    pub use super::components::*;
+    pub use super::turn_towards;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::time::TimeUpdateStrategy;
+
+    use super::super::bundles::*;
+    use super::super::KinematicPlugin;
+    use super::*;
+
+    use components::*;
+
+    #[test]
+    fn turn_across_wrap_around() {
+        let angle = turn_towards(0.9 * PI, -0.9 * PI, 0.1 * PI);
+
+        assert!((angle - PI).abs() < 1e-5);
+    }
+
+    #[test]
+    fn turn_limited_by_step() {
+        assert!((turn_towards(0., 1., 0.25) - 0.25).abs() < 1e-5);
+        assert!((turn_towards(0., -0.1, 0.25) + 0.1).abs() < 1e-5);
+    }
+
+    #[test]
+    fn arrives_behind() {
+        for reverse_thrust in [0., 0.5] {
+            let mut app = App::new();
+            app.add_plugins(MinimalPlugins)
+                .add_plugins(KinematicPlugin)
+                .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+                    16,
+                )));
+
+            let dest = Vec2::new(-60., 10.);
+            let vehicle = app
+                .world_mut()
+                .spawn((
+                    FullKinematic::default(),
+                    Heading(0.),
+                    SelfMoving { accel: 100. },
+                    Vehicle {
+                        max_yaw_rate: 3.,
+                        turning_radius: 20.,
+                        reverse_thrust,
+                        lateral_grip: 200.,
+                        arrival_radius: 2.,
+                    },
+                    MovingTo { dest },
+                ))
+                .id();
+
+            for _ in 0..1000 {
+                app.update();
+                if app.world().get::<MovingTo>(vehicle).is_none() {
+                    break;
+                }
+            }
+
+            let world = app.world();
+            assert!(world.get::<MovingTo>(vehicle).is_none());
+            assert!(world.get::<Position>(vehicle).unwrap().0.distance(dest) <= 2.);
+            assert!(world.get::<Velocity>(vehicle).unwrap().0.length() < 20.);
+        }
+    }
+}
@@ -2,8 +2,15 @@ use bevy::{app::PluginGroupBuilder, prelude::*};
 
 pub mod allegience;
 pub mod camera;
+pub mod feedback;
+pub mod formation;
+pub mod height_render;
 pub mod kinematic;
+pub mod order;
 pub mod player;
+pub mod pool;
+pub mod projectile;
+pub mod spatial;
 pub mod unit;
 
 #[derive(Default)]
@@ -15,9 +22,15 @@ impl PluginGroup for GamePlugins {
 
         group
             .add(kinematic::KinematicPlugin)
+            .add(height_render::HeightRenderPlugin)
             .add(allegience::AllegiencePlugin::default())
             .add(unit::UnitPlugin)
+            .add(order::OrderPlugin)
+            .add(formation::FormationPlugin)
+            .add(spatial::SpatialPlugin)
+            .add(projectile::ProjectilePlugin)
             .add(player::PlayerPlugin)
             .add(camera::CameraPlugin)
+            .add(feedback::CombatFeedbackPlugin)
     }
 }
@@ -0,0 +1,478 @@
+use std::{collections::VecDeque, time::Duration};
+
+use bevy::prelude::*;
+
+use super::kinematic::prelude::*;
+use super::projectile::prelude::*;
+use super::unit::prelude::*;
+
+pub mod components {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum Order {
+        MoveTo(Vec2),
+        /// Follow a target until it dies or is despawned.
+        Follow(Entity),
+        /// Chase a target until it dies, firing the unit's [`Weapon`] once in range.
+        Attack(Entity),
+        /// Stop and stay, until replaced by another order.
+        HoldPosition,
+        Wait(Duration),
+        /// Visit the waypoints in a loop, until replaced by another order.
+        Patrol(Vec<Vec2>),
+        /// Visit the waypoints back and forth, until replaced by another order.
+        PingPong(Vec<Vec2>),
+    }
+
+    #[derive(Debug, Clone, PartialEq, Default)]
+    pub(super) struct Progress {
+        pub started: bool,
+        pub waited: Duration,
+        pub waypoint: usize,
+        pub backwards: bool,
+    }
+
+    /// Queue of orders, translated one at a time into movement actions.
+    ///
+    /// Prerequisite: [`SelfMoving`]
+    #[derive(Debug, Clone, PartialEq, Component)]
+    pub struct Orders {
+        pub(super) queue: VecDeque<Order>,
+        pub(super) progress: Progress,
+        /// Distance from a destination at which it counts as reached.
+        pub tolerance: f32,
+    }
+    impl Default for Orders {
+        fn default() -> Self {
+            Self {
+                queue: VecDeque::new(),
+                progress: Progress::default(),
+                tolerance: 5.,
+            }
+        }
+    }
+    impl Orders {
+        pub fn new(order: Order) -> Self {
+            let mut orders = Self::default();
+            orders.push(order);
+            orders
+        }
+
+        pub fn current(&self) -> Option<&Order> {
+            self.queue.front()
+        }
+        pub fn iter(&self) -> impl Iterator<Item = &Order> {
+            self.queue.iter()
+        }
+        pub fn is_empty(&self) -> bool {
+            self.queue.is_empty()
+        }
+
+        /// Append an order after the queued ones.
+        pub fn push(&mut self, order: Order) {
+            self.queue.push_back(order);
+        }
+        /// Drop the queued orders and start this one instead.
+        pub fn replace(&mut self, order: Order) {
+            self.clear();
+            self.push(order);
+        }
+        /// Append or replace, e.g. depending on whether shift is held.
+        pub fn issue(&mut self, order: Order, append: bool) {
+            if append {
+                self.push(order);
+            } else {
+                self.replace(order);
+            }
+        }
+        pub fn clear(&mut self) {
+            self.queue.clear();
+            self.progress = Progress::default();
+        }
+
+        pub(super) fn advance(&mut self) -> Option<Order> {
+            self.progress = Progress::default();
+            self.queue.pop_front()
+        }
+    }
+}
+
+pub mod events {
+    use super::*;
+    use components::*;
+
+    #[derive(Debug, Event)]
+    pub struct OrderCompleted {
+        pub entity: Entity,
+        pub order: Order,
+    }
+}
+
+pub mod systems {
+    use super::*;
+    use components::*;
+    use events::*;
+
+    /// Fraction of its weapon's range at which an attacking unit keeps from its target.
+    const ATTACK_STANDOFF: f32 = 0.8;
+
+    fn move_to(commands: &mut Commands, entity: Entity, dest: Vec2) {
+        commands
+            .entity(entity)
+            .remove::<(Following, MovingIn, Decelerating)>()
+            .insert(MovingTo { dest });
+    }
+
+    fn stop(commands: &mut Commands, entity: Entity) {
+        commands
+            .entity(entity)
+            .remove::<(Following, MovingTo, MovingIn)>()
+            .insert(Decelerating);
+    }
+
+    /// Index of the next waypoint of a route.
+    fn next_waypoint(len: usize, progress: &mut Progress, ping_pong: bool) -> usize {
+        if !ping_pong {
+            return (progress.waypoint + 1) % len;
+        }
+        if len < 2 {
+            return 0;
+        }
+        if progress.waypoint == 0 {
+            progress.backwards = false;
+        } else if progress.waypoint == len - 1 {
+            progress.backwards = true;
+        }
+        if progress.backwards {
+            progress.waypoint - 1
+        } else {
+            progress.waypoint + 1
+        }
+    }
+
+    /// Start the current order of every unit, and advance the queue once it is completed.
+    ///
+    /// A destination is also reached once its [`MovingTo`] is removed, e.g. by a [`Vehicle`]
+    /// stopping within its arrival radius, which may be wider than the tolerance.
+    #[allow(clippy::type_complexity)]
+    pub fn update_orders(
+        mut commands: Commands,
+        mut query: Query<
+            (
+                Entity,
+                &Position,
+                &mut Orders,
+                Option<&Weapon>,
+                Has<MovingTo>,
+            ),
+            Without<Dead>,
+        >,
+        targets: Query<(&Position, Option<&HP>)>,
+        mut events: EventWriter<OrderCompleted>,
+        mut fire: EventWriter<FireWeapon>,
+        time: Res<Time>,
+    ) {
+        for (entity, pos, mut orders, weapon, moving) in query.iter_mut() {
+            let orders = orders.as_mut();
+            let Some(order) = orders.queue.front() else {
+                continue;
+            };
+            let progress = &mut orders.progress;
+            let arrived = progress.started && !moving;
+            let reached = |dest: Vec2| arrived || pos.0.distance(dest) <= orders.tolerance;
+
+            let completed = match order {
+                Order::MoveTo(dest) => {
+                    if !progress.started {
+                        move_to(&mut commands, entity, *dest);
+                    }
+                    reached(*dest)
+                }
+                Order::Follow(target) | Order::Attack(target) => {
+                    let attack = matches!(order, Order::Attack(_)).then_some(weapon);
+                    if !progress.started {
+                        let following = match attack {
+                            Some(weapon) => Following::new(*target).with_lead().with_standoff(
+                                weapon.map_or(0., |weapon| ATTACK_STANDOFF * weapon.range()),
+                            ),
+                            None => Following::new(*target),
+                        };
+                        commands
+                            .entity(entity)
+                            .remove::<(MovingIn, Decelerating)>()
+                            .insert((MovingTo { dest: pos.0 }, following));
+                    }
+                    match targets.get(*target) {
+                        Err(_) => true,
+                        Ok((_, hp)) if hp.is_some_and(HP::is_dead) => true,
+                        Ok((target_pos, _)) => {
+                            if let Some(Some(weapon)) = attack {
+                                if weapon.is_ready()
+                                    && pos.0.distance(target_pos.0) <= weapon.range()
+                                {
+                                    fire.send(FireWeapon {
+                                        shooter: entity,
+                                        aim: target_pos.0,
+                                        target: Some(*target),
+                                    });
+                                }
+                            }
+                            false
+                        }
+                    }
+                }
+                Order::HoldPosition => {
+                    if !progress.started {
+                        stop(&mut commands, entity);
+                    }
+                    false
+                }
+                Order::Wait(duration) => {
+                    if !progress.started {
+                        stop(&mut commands, entity);
+                    }
+                    progress.waited += time.delta();
+                    progress.waited >= *duration
+                }
+                Order::Patrol(route) | Order::PingPong(route) => {
+                    if route.is_empty() {
+                        true
+                    } else {
+                        let ping_pong = matches!(order, Order::PingPong(_));
+                        if progress.started && reached(route[progress.waypoint]) {
+                            progress.waypoint = next_waypoint(route.len(), progress, ping_pong);
+                            progress.started = false;
+                        }
+                        if !progress.started {
+                            move_to(&mut commands, entity, route[progress.waypoint]);
+                        }
+                        false
+                    }
+                }
+            };
+            progress.started = true;
+
+            if completed {
+                if let Some(order) = orders.advance() {
+                    events.send(OrderCompleted { entity, order });
+                }
+                if orders.is_empty() {
+                    stop(&mut commands, entity);
+                }
+            }
+        }
+    }
+
+    pub fn drop_orders_of_dead(
+        mut commands: Commands,
+        query: Query<Entity, (With<Orders>, Added<Dead>)>,
+    ) {
+        for entity in query.iter() {
+            commands.entity(entity).remove::<Orders>();
+        }
+    }
+}
+
+/// Attack orders fire through the [`ProjectilePlugin`], which owns [`FireWeapon`].
+pub struct OrderPlugin;
+
+impl Plugin for OrderPlugin {
+    fn build(&self, app: &mut App) {
+        use events::*;
+        use systems::*;
+
+        app.add_event::<OrderCompleted>().add_systems(
+            Update,
+            (drop_orders_of_dead, update_orders).before(KinematicSet::Steering),
+        );
+    }
+}
+
+pub mod prelude {
+    pub use super::components::*;
+    pub use super::events::*;
+
+    pub use super::OrderPlugin;
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    use components::*;
+    use events::*;
+
+    fn world() -> World {
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world.init_resource::<Events<OrderCompleted>>();
+        world.init_resource::<Events<FireWeapon>>();
+        world
+    }
+
+    fn completed(world: &mut World) -> Vec<Order> {
+        world
+            .resource_mut::<Events<OrderCompleted>>()
+            .drain()
+            .map(|event| event.order)
+            .collect()
+    }
+
+    #[test]
+    fn queueing() {
+        let mut orders = Orders::new(Order::MoveTo(Vec2::X));
+        orders.issue(Order::HoldPosition, true);
+        assert_eq!(orders.iter().count(), 2);
+        assert_eq!(orders.current(), Some(&Order::MoveTo(Vec2::X)));
+
+        orders.issue(Order::Wait(Duration::from_secs(1)), false);
+        assert_eq!(
+            orders.iter().collect::<Vec<_>>(),
+            vec![&Order::Wait(Duration::from_secs(1))]
+        );
+        assert_eq!(orders.advance(), Some(Order::Wait(Duration::from_secs(1))));
+        assert!(orders.is_empty());
+    }
+
+    #[test]
+    fn completion_advances_the_queue() {
+        let mut world = world();
+        let dest = Vec2::new(100., 0.);
+        let mut orders = Orders::new(Order::MoveTo(Vec2::new(3., 0.)));
+        orders.push(Order::MoveTo(dest));
+        let unit = world.spawn((Position(Vec2::ZERO), orders)).id();
+
+        // Already within tolerance of the first destination.
+        world.run_system_once(systems::update_orders);
+        assert_eq!(
+            completed(&mut world),
+            vec![Order::MoveTo(Vec2::new(3., 0.))]
+        );
+
+        world.run_system_once(systems::update_orders);
+        assert_eq!(world.get::<MovingTo>(unit).unwrap().dest, dest);
+        assert!(completed(&mut world).is_empty());
+
+        world.get_mut::<Position>(unit).unwrap().0 = dest;
+        world.run_system_once(systems::update_orders);
+        assert_eq!(completed(&mut world), vec![Order::MoveTo(dest)]);
+        assert!(world.get::<Orders>(unit).unwrap().is_empty());
+        assert!(world.get::<MovingTo>(unit).is_none());
+        assert!(world.get::<Decelerating>(unit).is_some());
+    }
+
+    #[test]
+    fn attack_fires_until_the_target_dies() {
+        let mut world = world();
+        let target = world
+            .spawn((Position(Vec2::new(50., 0.)), HP::new(10., 10.)))
+            .id();
+        let weapon = Weapon::new(
+            Projectile::new(1.),
+            100.,
+            Duration::from_secs(1),
+            Duration::from_secs(1),
+        );
+        let unit = world
+            .spawn((
+                Position(Vec2::ZERO),
+                Orders::new(Order::Attack(target)),
+                weapon,
+            ))
+            .id();
+
+        world.run_system_once(systems::update_orders);
+        let fired: Vec<_> = world
+            .resource_mut::<Events<FireWeapon>>()
+            .drain()
+            .map(|event| (event.shooter, event.target))
+            .collect();
+        assert_eq!(fired, vec![(unit, Some(target))]);
+        assert_eq!(world.get::<Following>(unit).unwrap().standoff, 80.);
+
+        world.get_mut::<HP>(target).unwrap().damage(10.);
+        world.run_system_once(systems::update_orders);
+        assert_eq!(completed(&mut world), vec![Order::Attack(target)]);
+    }
+
+    /// Destinations given one after the other, each reached as soon as it is given.
+    fn route(world: &mut World, unit: Entity, len: usize) -> Vec<Vec2> {
+        (0..len)
+            .map(|_| {
+                world.run_system_once(systems::update_orders);
+                let dest = world.get::<MovingTo>(unit).unwrap().dest;
+                world.get_mut::<Position>(unit).unwrap().0 = dest;
+                dest
+            })
+            .collect()
+    }
+
+    #[test]
+    fn patrols_loop_and_ping_pongs_turn_back() {
+        let (a, b, c) = (Vec2::X * 100., Vec2::Y * 100., Vec2::NEG_X * 100.);
+        let mut world = world();
+        let patrol = world
+            .spawn((
+                Position(Vec2::ZERO),
+                Orders::new(Order::Patrol(vec![a, b, c])),
+            ))
+            .id();
+        let ping_pong = world
+            .spawn((
+                Position(Vec2::ZERO),
+                Orders::new(Order::PingPong(vec![a, b, c])),
+            ))
+            .id();
+
+        assert_eq!(route(&mut world, patrol, 5), vec![a, b, c, a, b]);
+        assert_eq!(route(&mut world, ping_pong, 6), vec![a, b, c, b, a, b]);
+        // Never completed.
+        assert!(completed(&mut world).is_empty());
+    }
+
+    #[test]
+    fn vehicles_complete_moves_within_their_arrival_radius() {
+        use bevy::time::TimeUpdateStrategy;
+
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugins((KinematicPlugin, OrderPlugin))
+            .add_event::<FireWeapon>()
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+                16,
+            )));
+
+        let dest = Vec2::new(200., 0.);
+        let vehicle = app
+            .world_mut()
+            .spawn((
+                FullKinematic::default(),
+                Heading(0.),
+                SelfMoving { accel: 100. },
+                Vehicle {
+                    max_yaw_rate: 3.,
+                    turning_radius: 20.,
+                    reverse_thrust: 0.,
+                    lateral_grip: 200.,
+                    arrival_radius: 30.,
+                },
+                Orders::new(Order::MoveTo(dest)),
+            ))
+            .id();
+
+        for _ in 0..1000 {
+            app.update();
+            if app.world().get::<Orders>(vehicle).unwrap().is_empty() {
+                break;
+            }
+        }
+
+        let world = app.world();
+        assert!(world.get::<Orders>(vehicle).unwrap().is_empty());
+        // Stopped short of the tolerance.
+        assert!(world.get::<Position>(vehicle).unwrap().0.distance(dest) > 5.);
+    }
+}
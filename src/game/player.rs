@@ -1,9 +1,14 @@
+use std::time::Duration;
+
 use bevy::prelude::*;
 
 use super::allegience::prelude::*;
 use super::kinematic::prelude::*;
+use super::order::prelude::*;
 use super::unit::prelude::*;
 
+use crate::mouse::MousePosition;
+
 pub mod components {
     use super::*;
 
@@ -58,17 +63,30 @@ pub mod systems {
 
     // TODO: Make this configurable
     pub fn spawn_local_player(mut commands: Commands) {
+        commands.spawn((SpawnPoint::new(Faction::A.flag()), Position(Vec2::ZERO)));
         commands
             .spawn(LocalPlayerBundle::new(PlayerBundle {
                 unit: UnitBundleWithFaction::new(Faction::A, HP::full(100.), Radius(5.)),
                 ..Default::default()
             }))
-            .insert(SelfMoving { accel: 2000. });
+            .insert((
+                SelfMoving { accel: 2000. },
+                MaxSpeed(250.),
+                Sprint { multiplier: 1.6 },
+                Respawn {
+                    delay: Duration::from_secs(3),
+                    invulnerability: Duration::from_secs(2),
+                },
+            ));
     }
 
+    #[allow(clippy::type_complexity)]
     pub fn update_local_player_controlled(
         mut commands: Commands,
-        query: Query<Entity, (With<SelfMoving>, With<LocalPlayerControlled>)>,
+        mut query: Query<
+            (Entity, Option<&mut Orders>),
+            (With<SelfMoving>, With<LocalPlayerControlled>),
+        >,
         keyboard: Res<ButtonInput<KeyCode>>,
     ) {
         let w = keyboard.pressed(KeyCode::KeyW);
@@ -79,24 +97,78 @@ pub mod systems {
         let none = !w && !a && !s && !d;
 
         if none {
-            for entity in query.iter() {
-                commands
-                    .entity(entity)
-                    .remove::<MovingIn>()
-                    .insert(Decelerating);
+            for (entity, orders) in query.iter() {
+                let mut entity = commands.entity(entity);
+                entity.remove::<MovingIn>();
+                // Orders take over once the keys are released.
+                if orders.is_none_or(|orders| orders.is_empty()) {
+                    entity.insert(Decelerating);
+                }
             }
         } else {
             let x = d as i8 - a as i8;
             let y = w as i8 - s as i8;
             let dir = Vec2::new(x as f32, y as f32).normalize_or_zero();
 
-            for entity in query.iter() {
+            for (entity, orders) in query.iter_mut() {
+                // Manual movement overrides any orders.
+                if let Some(mut orders) = orders.filter(|orders| !orders.is_empty()) {
+                    orders.clear();
+                    commands.entity(entity).remove::<(MovingTo, Following)>();
+                }
                 commands
                     .entity(entity)
                     .remove::<Decelerating>()
                     .insert(MovingIn { dir });
             }
         }
+
+        // Only toggled on change, not to trigger change detection every frame.
+        if keyboard.just_pressed(KeyCode::Space) {
+            for (entity, _) in query.iter() {
+                commands.entity(entity).insert(Sprinting);
+            }
+        } else if keyboard.just_released(KeyCode::Space) {
+            for (entity, _) in query.iter() {
+                commands.entity(entity).remove::<Sprinting>();
+            }
+        }
+    }
+
+    /// Right click to move, shift to queue the move after the current orders.
+    #[allow(clippy::type_complexity)]
+    pub fn issue_local_player_orders(
+        mut commands: Commands,
+        mut query: Query<
+            (Entity, Option<&mut Orders>),
+            (With<SelfMoving>, With<LocalPlayerControlled>),
+        >,
+        camera: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+        mouse: Res<MousePosition>,
+        buttons: Res<ButtonInput<MouseButton>>,
+        keyboard: Res<ButtonInput<KeyCode>>,
+    ) {
+        if !buttons.just_pressed(MouseButton::Right) {
+            return;
+        }
+        let Ok((camera, global)) = camera.get_single() else {
+            return;
+        };
+        let Some(dest) = camera.viewport_to_world_2d(global, mouse.0) else {
+            return;
+        };
+        let append = keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+
+        for (entity, orders) in query.iter_mut() {
+            match orders {
+                Some(mut orders) => orders.issue(Order::MoveTo(dest), append),
+                None => {
+                    commands
+                        .entity(entity)
+                        .insert(Orders::new(Order::MoveTo(dest)));
+                }
+            }
+        }
     }
 }
 
@@ -113,9 +185,21 @@ impl Plugin for PlayerPlugin {
                 .contains_resource::<input::ButtonInput<KeyCode>>(),
             "Missing resource: ButtonInput<KeyCode>"
         );
+        assert!(
+            app.world()
+                .contains_resource::<input::ButtonInput<MouseButton>>(),
+            "Missing resource: ButtonInput<MouseButton>"
+        );
+        assert!(
+            app.world().contains_resource::<MousePosition>(),
+            "Missing resource: MousePosition"
+        );
 
-        app.add_systems(Startup, spawn_local_player)
-            .add_systems(Update, update_local_player_controlled);
+        app.add_systems(Startup, spawn_local_player).add_systems(
+            Update,
+            (update_local_player_controlled, issue_local_player_orders)
+                .before(KinematicSet::Steering),
+        );
     }
 }
 
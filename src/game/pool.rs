@@ -0,0 +1,364 @@
+//! Recycling of short-lived entities, e.g. projectiles and effects,
+//! instead of spawning and despawning them.
+
+use std::marker::PhantomData;
+
+use bevy::{
+    diagnostic::{Diagnostic, DiagnosticPath, Diagnostics, RegisterDiagnostic},
+    ecs::system::EntityCommands,
+    prelude::*,
+};
+
+/// A kind of pooled entities, each kind with its own [`Pool`](resources::Pool).
+pub trait PoolKind: Send + Sync + 'static {
+    /// Name in the pool diagnostics.
+    const NAME: &'static str;
+    /// Components kept on idle entities, e.g. their sprite.
+    /// Every other component is removed when an entity is recycled.
+    type Keep: Bundle;
+}
+
+pub mod components {
+    use super::*;
+
+    /// Tags an idle, hidden entity waiting in its pool.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Component)]
+    pub struct Pooled;
+
+    /// Tags an entity spawned by the pool of `K`, to return to it once recycled.
+    #[derive(Debug, Component)]
+    pub struct PoolMember<K: PoolKind>(PhantomData<K>);
+    impl<K: PoolKind> Default for PoolMember<K> {
+        fn default() -> Self {
+            Self(PhantomData)
+        }
+    }
+}
+
+pub mod resources {
+    use super::*;
+
+    use components::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub struct PoolStats {
+        /// Entities newly spawned.
+        pub spawned: u64,
+        /// Idle entities taken out of the pool.
+        pub reused: u64,
+        /// Entities returned to the pool.
+        pub recycled: u64,
+        /// Entities despawned, because the pool was disabled or full.
+        pub despawned: u64,
+    }
+    impl PoolStats {
+        /// Entities currently in use.
+        pub fn active(&self) -> u64 {
+            (self.spawned + self.reused).saturating_sub(self.recycled + self.despawned)
+        }
+        /// Entity allocations and deallocations, which pooling avoids.
+        pub fn churn(&self) -> u64 {
+            self.spawned + self.despawned
+        }
+        /// Fraction of acquired entities that were reused.
+        pub fn reuse_rate(&self) -> f32 {
+            let acquired = self.spawned + self.reused;
+            if acquired == 0 {
+                0.
+            } else {
+                self.reused as f32 / acquired as f32
+            }
+        }
+    }
+
+    #[derive(Debug, Resource)]
+    pub struct Pool<K: PoolKind> {
+        idle: Vec<Entity>,
+        /// Spawn and despawn naively, e.g. to compare churn.
+        pub enabled: bool,
+        /// Idle entities kept at most, the rest are despawned.
+        pub capacity: usize,
+        stats: PoolStats,
+        /// Churn at the last diagnostics measurement.
+        pub(super) reported_churn: u64,
+        _kind: PhantomData<K>,
+    }
+    impl<K: PoolKind> Default for Pool<K> {
+        fn default() -> Self {
+            Self::new(1024)
+        }
+    }
+    impl<K: PoolKind> Pool<K> {
+        pub fn new(capacity: usize) -> Self {
+            Self {
+                idle: Vec::new(),
+                enabled: true,
+                capacity,
+                stats: PoolStats::default(),
+                reported_churn: 0,
+                _kind: PhantomData,
+            }
+        }
+
+        pub fn stats(&self) -> PoolStats {
+            self.stats
+        }
+        pub fn idle(&self) -> usize {
+            self.idle.len()
+        }
+
+        /// Reuse an idle entity, or spawn one, with fresh components.
+        /// Return it with [`PoolCommandsExt::recycle`] instead of despawning it.
+        pub fn spawn<'a>(
+            &mut self,
+            commands: &'a mut Commands,
+            bundle: impl Bundle,
+        ) -> EntityCommands<'a> {
+            let mut idle = None;
+            if self.enabled {
+                while let Some(entity) = self.idle.pop() {
+                    // Idle entities may have been despawned behind the pool's back.
+                    if commands.get_entity(entity).is_some() {
+                        idle = Some(entity);
+                        break;
+                    }
+                }
+            }
+            match idle {
+                Some(entity) => {
+                    self.stats.reused += 1;
+                    let mut entity = commands.entity(entity);
+                    entity.remove::<Pooled>().insert(Visibility::Inherited);
+                    entity.insert(bundle);
+                    entity
+                }
+                None => {
+                    self.stats.spawned += 1;
+                    commands.spawn((bundle, PoolMember::<K>::default()))
+                }
+            }
+        }
+
+        /// Take back an entity, returning whether it is kept idle rather than despawned.
+        pub(super) fn release(&mut self, entity: Entity) -> bool {
+            if self.enabled && self.idle.len() < self.capacity {
+                self.stats.recycled += 1;
+                self.idle.push(entity);
+                true
+            } else {
+                self.stats.despawned += 1;
+                false
+            }
+        }
+
+        pub(super) fn forget(&mut self, entity: Entity) {
+            self.idle.retain(|&idle| idle != entity);
+        }
+    }
+}
+
+pub mod commands {
+    use super::*;
+
+    use components::*;
+    use resources::*;
+
+    pub trait PoolCommandsExt {
+        /// Return the entity to the pool of `K`, or despawn it if it isn't from there.
+        fn recycle<K: PoolKind>(&mut self) -> &mut Self;
+    }
+
+    impl PoolCommandsExt for EntityCommands<'_> {
+        fn recycle<K: PoolKind>(&mut self) -> &mut Self {
+            self.add(|entity: Entity, world: &mut World| {
+                let Some(entity_ref) = world.get_entity(entity) else {
+                    return;
+                };
+                if entity_ref.contains::<Pooled>() {
+                    return;
+                }
+                let member = entity_ref.contains::<PoolMember<K>>();
+                let kept = member
+                    && world
+                        .get_resource_mut::<Pool<K>>()
+                        .is_some_and(|mut pool| pool.release(entity));
+
+                if kept {
+                    world
+                        .entity_mut(entity)
+                        .retain::<(K::Keep, PoolMember<K>)>()
+                        .insert((Pooled, Visibility::Hidden));
+                } else {
+                    world.despawn(entity);
+                }
+            })
+        }
+    }
+}
+
+pub(super) mod systems {
+    use super::*;
+    use components::*;
+    use resources::*;
+
+    /// Forget idle entities that were despawned by something else.
+    pub fn forget_despawned_pooled<K: PoolKind>(
+        mut pool: ResMut<Pool<K>>,
+        mut removed: RemovedComponents<PoolMember<K>>,
+    ) {
+        for entity in removed.read() {
+            pool.forget(entity);
+        }
+    }
+
+    pub fn measure_pool_diagnostics<K: PoolKind>(
+        mut pool: ResMut<Pool<K>>,
+        mut diagnostics: Diagnostics,
+    ) {
+        let stats = pool.stats();
+        let churn = stats.churn() - pool.reported_churn;
+        pool.reported_churn = stats.churn();
+
+        diagnostics.add_measurement(&diagnostic_path::<K>("active"), || stats.active() as f64);
+        diagnostics.add_measurement(&diagnostic_path::<K>("idle"), || pool.idle() as f64);
+        diagnostics.add_measurement(&diagnostic_path::<K>("churn"), || churn as f64);
+    }
+}
+
+/// Path of a diagnostic of the pool of `K`, e.g. `pool/projectile/churn`,
+/// the spawns and despawns per frame.
+pub fn diagnostic_path<K: PoolKind>(name: &str) -> DiagnosticPath {
+    DiagnosticPath::new(format!("pool/{}/{name}", K::NAME))
+}
+
+pub struct PoolPlugin<K: PoolKind> {
+    pub capacity: usize,
+    _kind: PhantomData<K>,
+}
+impl<K: PoolKind> PoolPlugin<K> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            _kind: PhantomData,
+        }
+    }
+}
+impl<K: PoolKind> Default for PoolPlugin<K> {
+    fn default() -> Self {
+        Self::new(1024)
+    }
+}
+
+impl<K: PoolKind> Plugin for PoolPlugin<K> {
+    fn build(&self, app: &mut App) {
+        use resources::*;
+        use systems::*;
+
+        for name in ["active", "idle", "churn"] {
+            app.register_diagnostic(Diagnostic::new(diagnostic_path::<K>(name)));
+        }
+
+        app.insert_resource(Pool::<K>::new(self.capacity))
+            .add_systems(
+                Last,
+                (forget_despawned_pooled::<K>, measure_pool_diagnostics::<K>).chain(),
+            );
+    }
+}
+
+pub mod prelude {
+    pub use super::commands::*;
+    pub use super::components::*;
+    pub use super::resources::*;
+
+    pub use super::{PoolKind, PoolPlugin};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use commands::*;
+    use components::*;
+    use resources::*;
+
+    struct Bullet;
+    impl PoolKind for Bullet {
+        const NAME: &'static str = "bullet";
+        type Keep = Name;
+    }
+
+    #[derive(Component)]
+    struct Payload;
+
+    /// Fire and recycle waves of bullets, returning the stats and the world.
+    fn fire_waves(enabled: bool) -> (PoolStats, World) {
+        let mut world = World::new();
+        let mut pool = Pool::<Bullet>::new(16);
+        pool.enabled = enabled;
+        world.insert_resource(pool);
+
+        for _ in 0..10 {
+            let bullets: Vec<_> = world.resource_scope(|world, mut pool: Mut<Pool<Bullet>>| {
+                let mut commands = world.commands();
+                (0..8)
+                    .map(|_| {
+                        pool.spawn(&mut commands, (Name::new("bullet"), Payload))
+                            .id()
+                    })
+                    .collect()
+            });
+            world.flush();
+            for bullet in bullets {
+                world.commands().entity(bullet).recycle::<Bullet>();
+            }
+            world.flush();
+        }
+
+        (world.resource::<Pool<Bullet>>().stats(), world)
+    }
+
+    #[test]
+    fn pooling_avoids_churn() {
+        let (pooled, mut world) = fire_waves(true);
+        let (naive, _) = fire_waves(false);
+
+        assert_eq!(pooled.churn(), 8);
+        assert_eq!(pooled.reused, 72);
+        assert_eq!(naive.churn(), 160);
+        assert_eq!(pooled.active(), 0);
+        assert_eq!(naive.active(), 0);
+
+        // Idle bullets keep only what the kind keeps, and are hidden.
+        let mut idle = world.query::<(&Pooled, &Name, &Visibility, Has<Payload>)>();
+        assert_eq!(idle.iter(&world).len(), 8);
+        for (_, _, visibility, payload) in idle.iter(&world) {
+            assert_eq!(visibility, Visibility::Hidden);
+            assert!(!payload);
+        }
+    }
+
+    #[test]
+    fn despawned_idle_entities_are_skipped() {
+        let (_, mut world) = fire_waves(true);
+        let mut idle = world.query_filtered::<Entity, With<Pooled>>();
+        let idle: Vec<_> = idle.iter(&world).collect();
+        for &entity in &idle[1..] {
+            world.despawn(entity);
+        }
+
+        let bullets: Vec<_> = world.resource_scope(|world, mut pool: Mut<Pool<Bullet>>| {
+            let mut commands = world.commands();
+            (0..2)
+                .map(|_| pool.spawn(&mut commands, Name::new("bullet")).id())
+                .collect()
+        });
+        world.flush();
+
+        assert!(idle.contains(&bullets[0]));
+        assert!(!idle.contains(&bullets[1]));
+        let pool = world.resource::<Pool<Bullet>>();
+        assert_eq!(pool.idle(), 0);
+        assert_eq!((pool.stats().reused, pool.stats().spawned), (73, 9));
+    }
+}
@@ -0,0 +1,312 @@
+//! Homing missiles: projectiles steered as [`Vehicle`]s [`Following`] their target.
+
+use bevy::prelude::*;
+
+use super::super::allegience::prelude::*;
+use super::super::kinematic::prelude::*;
+use super::super::pool::prelude::*;
+use super::super::spatial::prelude::*;
+use super::super::unit::prelude::*;
+use super::can_hit;
+use super::components::*;
+
+pub mod components {
+    use super::*;
+
+    /// Guidance of a homing missile.
+    ///
+    /// Prerequisite: [`Projectile`]
+    #[derive(Debug, Clone, Copy, PartialEq, Component)]
+    pub struct Homing {
+        pub guidance: Pursuit,
+        pub accel: f32,
+        /// Maximum turning speed, in radians per second.
+        pub turn_rate: f32,
+        /// Distance within which a new target is acquired once the target dies.
+        /// `None` disables retargeting.
+        pub retarget_range: Option<f32>,
+        /// How many times as attractive as the target a [`Decoy`] must be to divert the missile.
+        pub decoy_resistance: f32,
+    }
+    impl Homing {
+        pub fn new(accel: f32, turn_rate: f32) -> Self {
+            Self {
+                guidance: Pursuit::Proportional { gain: 4. },
+                accel,
+                turn_rate,
+                retarget_range: None,
+                decoy_resistance: 1.,
+            }
+        }
+        pub fn with_guidance(mut self, guidance: Pursuit) -> Self {
+            self.guidance = guidance;
+            self
+        }
+        pub fn with_retarget(mut self, range: f32) -> Self {
+            self.retarget_range = Some(range);
+            self
+        }
+        pub fn with_decoy_resistance(mut self, resistance: f32) -> Self {
+            self.decoy_resistance = resistance;
+            self
+        }
+
+        pub(in super::super) fn following(&self, target: Entity) -> Following {
+            Following {
+                pursuit: self.guidance,
+                ..Following::new(target).on_lost(OnTargetLost::GoToLastKnown)
+            }
+        }
+    }
+
+    /// Countermeasure diverting hostile homing missiles towards itself,
+    /// which are destroyed once they reach it.
+    ///
+    /// Prerequisite: [`Position`]
+    #[derive(Debug, Clone, Copy, PartialEq, Component)]
+    pub struct Decoy {
+        /// Attraction relative to a regular target at the same distance.
+        pub strength: f32,
+        /// Distance within which missiles are diverted.
+        pub range: f32,
+        /// Distance at which a missile is caught.
+        pub radius: f32,
+    }
+}
+
+pub mod bundles {
+    use super::*;
+
+    use components::*;
+
+    /// Flight components of a homing missile, on top of a
+    /// [`ProjectileBundle`](super::super::bundles::ProjectileBundle).
+    #[derive(Debug, Bundle)]
+    pub struct HomingBundle {
+        pub homing: Homing,
+        pub heading: Heading,
+        pub vehicle: Vehicle,
+        pub self_moving: SelfMoving,
+        pub max_speed: MaxSpeed,
+        pub acceleration: Acceleration,
+        pub propulsion: Propulsion,
+    }
+    impl HomingBundle {
+        pub fn new(homing: Homing, dir: Vec2, max_speed: f32) -> Self {
+            Self {
+                homing,
+                heading: Heading::from_dir(dir),
+                vehicle: Vehicle {
+                    max_yaw_rate: homing.turn_rate,
+                    turning_radius: 0.,
+                    reverse_thrust: 0.,
+                    // Fly where it points.
+                    lateral_grip: homing.accel,
+                    arrival_radius: 0.,
+                },
+                self_moving: SelfMoving {
+                    accel: homing.accel,
+                },
+                max_speed: MaxSpeed(max_speed),
+                acceleration: Acceleration::default(),
+                propulsion: Propulsion::default(),
+            }
+        }
+    }
+}
+
+pub(super) mod systems {
+    use super::*;
+    use components::*;
+
+    /// Acquire the nearest hostile unit once the target is dead or gone.
+    #[allow(clippy::type_complexity)]
+    pub fn retarget_homing(
+        mut commands: Commands,
+        query: Query<(
+            Entity,
+            &Projectile,
+            &Position,
+            Option<&Faction>,
+            &Homing,
+            Option<&Following>,
+        )>,
+        targets: Query<(Option<&Faction>, Option<&HP>)>,
+        index: Res<SpatialIndex>,
+        relationships: Res<FactionRelationships>,
+    ) {
+        for (entity, projectile, pos, faction, homing, following) in query.iter() {
+            let Some(range) = homing.retarget_range else {
+                continue;
+            };
+            let tracking = following.is_some_and(|following| {
+                targets
+                    .get(following.target)
+                    .is_ok_and(|(_, hp)| !hp.is_some_and(HP::is_dead))
+            });
+            if tracking {
+                continue;
+            }
+
+            let nearest = index
+                .query_circle(pos.0, range)
+                .filter(|&(target, ..)| Some(target) != projectile.owner)
+                .filter(|&(target, ..)| {
+                    targets.get(target).is_ok_and(|(target_faction, hp)| {
+                        can_hit(&relationships, faction, target_faction, hp)
+                    })
+                })
+                .min_by(|a, b| {
+                    let a = a.1.distance_squared(pos.0);
+                    let b = b.1.distance_squared(pos.0);
+                    a.total_cmp(&b)
+                });
+            if let Some((target, ..)) = nearest {
+                commands
+                    .entity(entity)
+                    .insert((MovingTo { dest: pos.0 }, homing.following(target)));
+            }
+        }
+    }
+
+    /// Divert missiles to decoys more attractive than their target,
+    /// and destroy those that reach their decoy.
+    pub fn divert_homing_to_decoys(
+        mut commands: Commands,
+        mut missiles: Query<(Entity, &Position, Option<&Faction>, &Homing, &mut Following)>,
+        decoys: Query<(Entity, &Position, &Decoy, Option<&Faction>)>,
+        targets: Query<(&Position, Option<&Decoy>)>,
+        relationships: Res<FactionRelationships>,
+    ) {
+        for (entity, pos, faction, homing, mut following) in missiles.iter_mut() {
+            let attraction = |target_pos: Vec2, strength: f32| {
+                strength / target_pos.distance(pos.0).max(f32::EPSILON)
+            };
+            let current = match targets.get(following.target) {
+                Ok((target_pos, Some(decoy))) => {
+                    if target_pos.0.distance(pos.0) <= decoy.radius {
+                        commands.entity(entity).recycle::<Projectile>();
+                        continue;
+                    }
+                    attraction(target_pos.0, decoy.strength)
+                }
+                Ok((target_pos, None)) => attraction(target_pos.0, homing.decoy_resistance),
+                Err(_) => 0.,
+            };
+
+            let best = decoys
+                .iter()
+                .filter(|(_, decoy_pos, decoy, _)| decoy_pos.0.distance(pos.0) <= decoy.range)
+                .filter(|(.., decoy_faction)| {
+                    relationships.relationship_between(faction.copied(), decoy_faction.copied())
+                        != Relationship::Allied
+                })
+                .map(|(decoy, decoy_pos, Decoy { strength, .. }, _)| {
+                    (decoy, attraction(decoy_pos.0, *strength))
+                })
+                .max_by(|a, b| a.1.total_cmp(&b.1));
+            if let Some((decoy, decoy_attraction)) = best {
+                if decoy != following.target && decoy_attraction > current {
+                    following.target = decoy;
+                }
+            }
+        }
+    }
+}
+
+pub mod prelude {
+    pub use super::bundles::*;
+    pub use super::components::*;
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    use components::*;
+
+    fn world() -> World {
+        let mut world = World::new();
+        world.insert_resource(FactionRelationships::with_default(Relationship::Hostile));
+        world.init_resource::<SpatialIndex>();
+        world
+    }
+
+    fn missile(world: &mut World, homing: Homing, target: Entity) -> Entity {
+        world
+            .spawn((
+                Projectile::new(1.),
+                Position(Vec2::ZERO),
+                Faction::A,
+                homing,
+                homing.following(target),
+            ))
+            .id()
+    }
+
+    #[test]
+    fn retargets_once_the_target_dies() {
+        let mut world = world();
+        let mut unit = |world: &mut World, x: f32, faction: Faction, hp: f32| {
+            let pos = Vec2::new(x, 0.);
+            let entity = world.spawn((Position(pos), faction, HP::new(hp, 10.))).id();
+            world.resource_mut::<SpatialIndex>().insert(entity, pos, 1.);
+            entity
+        };
+        let target = unit(&mut world, 10., Faction::B, 10.);
+        let ally = unit(&mut world, 5., Faction::A, 10.);
+        let near = unit(&mut world, 30., Faction::B, 10.);
+        let far = unit(&mut world, 60., Faction::B, 10.);
+        let missile = missile(
+            &mut world,
+            Homing::new(100., 1.).with_retarget(100.),
+            target,
+        );
+
+        world.run_system_once(systems::retarget_homing);
+        assert_eq!(world.get::<Following>(missile).unwrap().target, target);
+
+        world.get_mut::<HP>(target).unwrap().value = 0.;
+        world.run_system_once(systems::retarget_homing);
+        let following = world.get::<Following>(missile).unwrap();
+        assert_eq!(following.target, near);
+        assert_ne!(following.target, ally);
+        assert_ne!(following.target, far);
+    }
+
+    #[test]
+    fn diverted_to_attractive_decoys() {
+        let mut world = world();
+        let target = world
+            .spawn((Position(Vec2::new(100., 0.)), Faction::B))
+            .id();
+        let decoy = world
+            .spawn((
+                Position(Vec2::new(50., 0.)),
+                Faction::B,
+                Decoy {
+                    strength: 5.,
+                    range: 200.,
+                    radius: 5.,
+                },
+            ))
+            .id();
+        let fooled = missile(&mut world, Homing::new(100., 1.), target);
+        let resistant = missile(
+            &mut world,
+            Homing::new(100., 1.).with_decoy_resistance(100.),
+            target,
+        );
+
+        world.run_system_once(systems::divert_homing_to_decoys);
+        assert_eq!(world.get::<Following>(fooled).unwrap().target, decoy);
+        assert_eq!(world.get::<Following>(resistant).unwrap().target, target);
+
+        // Caught once it reaches the decoy.
+        world.get_mut::<Position>(fooled).unwrap().0 = Vec2::new(48., 0.);
+        world.run_system_once(systems::divert_homing_to_decoys);
+        assert!(world.get_entity(fooled).is_none());
+    }
+}
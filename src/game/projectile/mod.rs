@@ -0,0 +1,530 @@
+use std::time::Duration;
+
+use bevy::{
+    prelude::*,
+    sprite::{MaterialMesh2dBundle, Mesh2dHandle},
+};
+
+use super::allegience::prelude::*;
+use super::kinematic::prelude::*;
+use super::pool::prelude::*;
+use super::spatial::prelude::*;
+use super::unit::prelude::*;
+
+pub mod homing;
+pub mod weapon;
+
+/// Whether a projectile of `faction` hits a target, i.e. it is alive and not an ally.
+pub(crate) fn can_hit(
+    relationships: &FactionRelationships,
+    faction: Option<&Faction>,
+    target_faction: Option<&Faction>,
+    hp: Option<&HP>,
+) -> bool {
+    let allied = relationships.relationship_between(faction.copied(), target_faction.copied())
+        == Relationship::Allied;
+    !allied && !hp.is_some_and(HP::is_dead)
+}
+
+pub mod components {
+    use super::*;
+
+    /// Travels in a straight line, hitting units along its path.
+    ///
+    /// Prerequisite: [`Position`], [`Velocity`], [`ProjectileLifetime`]
+    #[derive(Debug, Clone, PartialEq, Component)]
+    pub struct Projectile {
+        pub damage: f32,
+        pub kind: DamageKind,
+        /// Impulse applied to hit units, along the direction of travel.
+        pub knockback: f32,
+        /// Number of further units it passes through after a hit.
+        pub pierce: u32,
+        /// Radius of the projectile itself.
+        pub radius: f32,
+        /// Never hit by its own projectiles.
+        pub owner: Option<Entity>,
+        /// Where the projectile was at the last check, to sweep from.
+        pub(super) last: Option<Vec2>,
+        /// Units already hit, not to be hit twice while piercing.
+        pub(super) hit: Vec<Entity>,
+    }
+    impl Projectile {
+        pub fn new(damage: f32) -> Self {
+            Self {
+                damage,
+                kind: DamageKind::default(),
+                knockback: 0.,
+                pierce: 0,
+                radius: 1.,
+                owner: None,
+                last: None,
+                hit: Vec::new(),
+            }
+        }
+        pub fn of_kind(mut self, kind: DamageKind) -> Self {
+            self.kind = kind;
+            self
+        }
+        pub fn with_knockback(mut self, knockback: f32) -> Self {
+            self.knockback = knockback;
+            self
+        }
+        pub fn with_pierce(mut self, pierce: u32) -> Self {
+            self.pierce = pierce;
+            self
+        }
+        pub fn with_radius(mut self, radius: f32) -> Self {
+            self.radius = radius;
+            self
+        }
+        pub fn with_owner(mut self, owner: Entity) -> Self {
+            self.owner = Some(owner);
+            self
+        }
+    }
+
+    impl PoolKind for Projectile {
+        const NAME: &'static str = "projectile";
+        /// The sprite, so it isn't inserted anew for every shot.
+        type Keep = (
+            Transform,
+            GlobalTransform,
+            Visibility,
+            InheritedVisibility,
+            ViewVisibility,
+            Mesh2dHandle,
+            Handle<ColorMaterial>,
+        );
+    }
+
+    /// Recycles the projectile once finished.
+    #[derive(Debug, Clone, PartialEq, Eq, Component)]
+    pub struct ProjectileLifetime(pub Timer);
+    impl ProjectileLifetime {
+        pub fn new(duration: Duration) -> Self {
+            Self(Timer::new(duration, TimerMode::Once))
+        }
+    }
+}
+
+pub mod bundles {
+    use super::*;
+
+    use components::*;
+
+    /// Insert a [`Faction`] along with it, for its allies not to be hit.
+    /// Without one, the relationships of unaligned units apply.
+    #[derive(Debug, Bundle)]
+    pub struct ProjectileBundle {
+        pub projectile: Projectile,
+        pub lifetime: ProjectileLifetime,
+        pub position: Position,
+        pub velocity: Velocity,
+    }
+    impl ProjectileBundle {
+        pub fn new(projectile: Projectile, pos: Vec2, vel: Vec2, lifetime: Duration) -> Self {
+            Self {
+                projectile: Projectile {
+                    last: Some(pos),
+                    ..projectile
+                },
+                lifetime: ProjectileLifetime::new(lifetime),
+                position: Position(pos),
+                velocity: Velocity(vel),
+            }
+        }
+    }
+}
+
+pub mod resources {
+    use super::*;
+
+    /// Shared assets of every [`Projectile`](super::components::Projectile).
+    #[derive(Debug, Resource)]
+    pub struct ProjectileAssets {
+        pub mesh: Mesh2dHandle,
+        /// One per [`Faction`], by [`Faction::index`].
+        pub materials: Vec<Handle<ColorMaterial>>,
+        pub unaligned: Handle<ColorMaterial>,
+    }
+    impl ProjectileAssets {
+        pub fn material(&self, faction: Option<Faction>) -> Handle<ColorMaterial> {
+            faction
+                .map_or(&self.unaligned, |faction| &self.materials[faction.index()])
+                .clone()
+        }
+    }
+    impl FromWorld for ProjectileAssets {
+        fn from_world(world: &mut World) -> Self {
+            let mesh = world.resource_mut::<Assets<Mesh>>().add(Circle::new(1.));
+            let mut materials = world.resource_mut::<Assets<ColorMaterial>>();
+            let unaligned = materials.add(Faction::color_opt(None));
+            let materials = Faction::iter_once()
+                .map(|faction| materials.add(faction.color()))
+                .collect();
+            Self {
+                mesh: Mesh2dHandle(mesh),
+                materials,
+                unaligned,
+            }
+        }
+    }
+}
+
+pub mod events {
+    use super::*;
+
+    #[derive(Debug, Event)]
+    pub struct ProjectileHit {
+        pub projectile: Entity,
+        pub target: Entity,
+        pub owner: Option<Entity>,
+        /// Faction of the projectile, whose allies are spared.
+        pub faction: Option<Faction>,
+        pub damage: f32,
+        pub kind: DamageKind,
+        pub knockback: Vec2,
+        /// Where the projectile was when it touched the target.
+        pub point: Vec2,
+        pub dir: Vec2,
+    }
+}
+
+pub mod systems {
+    use super::*;
+    use components::*;
+    use events::*;
+    use resources::*;
+
+    /// Reused projectiles keep their sprite, which is only updated.
+    #[allow(clippy::type_complexity)]
+    pub fn add_sprite_to_projectiles(
+        mut commands: Commands,
+        mut query: Query<
+            (
+                Entity,
+                &Projectile,
+                Option<&Faction>,
+                &Position,
+                Option<(&Mesh2dHandle, &mut Handle<ColorMaterial>, &mut Transform)>,
+            ),
+            Added<Projectile>,
+        >,
+        assets: Res<ProjectileAssets>,
+    ) {
+        for (entity, projectile, faction, pos, sprite) in query.iter_mut() {
+            let material = assets.material(faction.copied());
+            let transform = Transform::from_translation(pos.0.extend(0.5))
+                .with_scale(Vec3::splat(projectile.radius));
+            match sprite {
+                Some((_, mut kept_material, mut kept_transform)) => {
+                    kept_material.set_if_neq(material);
+                    *kept_transform = transform;
+                }
+                None => {
+                    commands.entity(entity).insert(MaterialMesh2dBundle {
+                        mesh: assets.mesh.clone(),
+                        material,
+                        transform,
+                        ..default()
+                    });
+                }
+            }
+        }
+    }
+
+    /// Sweep every projectile along its path since the last check, so fast ones don't tunnel.
+    /// [`Airborne`] projectiles and targets are passed over.
+    #[allow(clippy::type_complexity)]
+    pub fn update_projectile_hits(
+        mut commands: Commands,
+        mut projectiles: Query<(
+            Entity,
+            &mut Projectile,
+            Option<&Faction>,
+            &Position,
+            &Velocity,
+            Has<Airborne>,
+        )>,
+        targets: Query<(Option<&Faction>, Option<&HP>), Without<Airborne>>,
+        index: Res<SpatialIndex>,
+        relationships: Res<FactionRelationships>,
+        mut events: EventWriter<ProjectileHit>,
+    ) {
+        for (entity, mut projectile, faction, pos, vel, airborne) in projectiles.iter_mut() {
+            let start = projectile.last.unwrap_or(pos.0);
+            projectile.last = Some(pos.0);
+            // Flying over units, e.g. a lobbed grenade.
+            if airborne {
+                continue;
+            }
+
+            for (target, t) in index.query_sweep(start, pos.0, projectile.radius) {
+                if Some(target) == projectile.owner || projectile.hit.contains(&target) {
+                    continue;
+                }
+                let Ok((target_faction, hp)) = targets.get(target) else {
+                    continue;
+                };
+                if !can_hit(&relationships, faction, target_faction, hp) {
+                    continue;
+                }
+
+                projectile.hit.push(target);
+                events.send(ProjectileHit {
+                    projectile: entity,
+                    target,
+                    owner: projectile.owner,
+                    faction: faction.copied(),
+                    damage: projectile.damage,
+                    kind: projectile.kind,
+                    knockback: vel.0.normalize_or_zero() * projectile.knockback,
+                    point: start.lerp(pos.0, t),
+                    dir: vel.0.normalize_or_zero(),
+                });
+
+                if projectile.pierce == 0 {
+                    commands.entity(entity).recycle::<Projectile>();
+                    break;
+                }
+                projectile.pierce -= 1;
+            }
+        }
+    }
+
+    pub fn deal_projectile_damage(
+        mut hits: EventReader<ProjectileHit>,
+        mut damage: EventWriter<DealDamage>,
+    ) {
+        for hit in hits.read() {
+            let mut request = DealDamage::new(hit.target, hit.damage)
+                .of_kind(hit.kind)
+                .with_knockback(hit.knockback)
+                .grounded();
+            request.source = hit.owner;
+            request.source_faction = hit.faction;
+            damage.send(request);
+        }
+    }
+
+    pub fn tick_down_projectile_lifetime(
+        mut commands: Commands,
+        mut query: Query<(Entity, &mut ProjectileLifetime)>,
+        time: Res<Time>,
+    ) {
+        for (entity, mut lifetime) in query.iter_mut() {
+            if lifetime.0.tick(time.delta()).finished() {
+                commands.entity(entity).recycle::<Projectile>();
+            }
+        }
+    }
+}
+
+pub struct ProjectilePlugin;
+
+impl Plugin for ProjectilePlugin {
+    fn build(&self, app: &mut App) {
+        use components::*;
+        use events::*;
+        use homing::systems::*;
+        use resources::*;
+        use systems::*;
+        use weapon::{events::*, systems::*};
+
+        app.add_plugins(PoolPlugin::<Projectile>::default())
+            .init_resource::<ProjectileAssets>()
+            .add_event::<ProjectileHit>()
+            .add_event::<FireWeapon>()
+            .add_systems(
+                Update,
+                (
+                    (tick_down_weapon_cooldown, fire_weapons).chain(),
+                    (retarget_homing, divert_homing_to_decoys).chain(),
+                )
+                    .after(SpatialSet)
+                    .before(KinematicSet::Sync),
+            )
+            .add_systems(
+                Update,
+                (
+                    add_sprite_to_projectiles,
+                    (
+                        tick_down_projectile_lifetime,
+                        update_projectile_hits,
+                        deal_projectile_damage,
+                    )
+                        .chain()
+                        .after(SpatialSet)
+                        .before(KinematicSet::Sync),
+                ),
+            );
+    }
+}
+
+pub mod prelude {
+    pub use super::bundles::*;
+    pub use super::components::*;
+    pub use super::events::*;
+    pub use super::homing::prelude::*;
+    pub use super::weapon::prelude::*;
+
+    pub use super::ProjectilePlugin;
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    use bundles::*;
+    use components::*;
+    use events::*;
+    use weapon::prelude::*;
+
+    fn world() -> World {
+        let mut world = World::new();
+        world.insert_resource(FactionRelationships::with_default(Relationship::Hostile));
+        world.init_resource::<SpatialIndex>();
+        world.init_resource::<Events<ProjectileHit>>();
+        world.insert_resource(Pool::<Projectile>::new(8));
+        world
+    }
+
+    fn unit(world: &mut World, x: f32, faction: Faction) -> Entity {
+        let pos = Vec2::new(x, 0.);
+        let entity = world.spawn((Position(pos), faction, HP::full(10.))).id();
+        world.resource_mut::<SpatialIndex>().insert(entity, pos, 1.);
+        entity
+    }
+
+    /// Fire a projectile of faction A from the origin, which has already flown 100 units.
+    fn fire(world: &mut World, projectile: Projectile) -> Entity {
+        let bundle = (
+            ProjectileBundle::new(
+                projectile,
+                Vec2::ZERO,
+                Vec2::new(100., 0.),
+                Duration::from_secs(1),
+            ),
+            Faction::A,
+        );
+        let entity = world.resource_scope(|world, mut pool: Mut<Pool<Projectile>>| {
+            pool.spawn(&mut world.commands(), bundle).id()
+        });
+        world.flush();
+        world.get_mut::<Position>(entity).unwrap().0 = Vec2::new(100., 0.);
+        entity
+    }
+
+    fn hits(world: &mut World) -> Vec<(Entity, Entity)> {
+        world.run_system_once(systems::update_projectile_hits);
+        world
+            .resource_mut::<Events<ProjectileHit>>()
+            .drain()
+            .map(|hit| (hit.projectile, hit.target))
+            .collect()
+    }
+
+    #[test]
+    fn hits_pierce_enemies_in_order() {
+        let mut world = world();
+        let owner = unit(&mut world, 10., Faction::B);
+        let _ally = unit(&mut world, 20., Faction::A);
+        let first = unit(&mut world, 30., Faction::B);
+        let flyer = unit(&mut world, 35., Faction::B);
+        world.entity_mut(flyer).insert(Airborne);
+        let second = unit(&mut world, 40., Faction::B);
+        let _third = unit(&mut world, 50., Faction::B);
+        let projectile = fire(
+            &mut world,
+            Projectile::new(1.).with_owner(owner).with_pierce(1),
+        );
+
+        // Past its owner, its ally and the flyer, and through one enemy into the next.
+        assert_eq!(
+            hits(&mut world),
+            vec![(projectile, first), (projectile, second)]
+        );
+        assert!(world.get::<Pooled>(projectile).is_some());
+    }
+
+    #[test]
+    fn hits_each_target_once() {
+        let mut world = world();
+        let enemy = unit(&mut world, 50., Faction::B);
+        let projectile = fire(&mut world, Projectile::new(1.).with_pierce(5));
+        assert_eq!(hits(&mut world), vec![(projectile, enemy)]);
+
+        // Swept over the same enemy again.
+        world.get_mut::<Projectile>(projectile).unwrap().last = Some(Vec2::ZERO);
+        assert!(hits(&mut world).is_empty());
+        assert_eq!(world.get::<Projectile>(projectile).unwrap().pierce, 4);
+    }
+
+    #[test]
+    fn airborne_projectiles_pass_over() {
+        let mut world = world();
+        unit(&mut world, 50., Faction::B);
+        let projectile = fire(&mut world, Projectile::new(1.));
+        world.entity_mut(projectile).insert(Airborne);
+
+        assert!(hits(&mut world).is_empty());
+        assert!(world.get::<Pooled>(projectile).is_none());
+    }
+
+    #[test]
+    fn schedule_builds_and_fires() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<Assets<Mesh>>()
+            .init_resource::<Assets<ColorMaterial>>()
+            .insert_resource(FactionRelationships::with_default(Relationship::Hostile))
+            .add_event::<DealDamage>()
+            .add_plugins((KinematicPlugin, SpatialPlugin, ProjectilePlugin));
+
+        let shooter = app
+            .world_mut()
+            .spawn((
+                Position(Vec2::ZERO),
+                Velocity(Vec2::new(10., 0.)),
+                Weapon::new(
+                    Projectile::new(1.),
+                    100.,
+                    Duration::from_secs(1),
+                    Duration::from_secs(1),
+                ),
+            ))
+            .id();
+        let dead = app
+            .world_mut()
+            .spawn((
+                Position(Vec2::ZERO),
+                Weapon::new(
+                    Projectile::new(1.),
+                    100.,
+                    Duration::from_secs(1),
+                    Duration::from_secs(1),
+                ),
+                Dead,
+            ))
+            .id();
+        app.update();
+        for shooter in [shooter, dead] {
+            app.world_mut().send_event(FireWeapon {
+                shooter,
+                aim: Vec2::new(1000., 0.),
+                target: None,
+            });
+        }
+        app.update();
+
+        // Launched at the weapon's speed, on top of the shooter's velocity, and not by the dead.
+        let mut projectiles = app
+            .world_mut()
+            .query_filtered::<&Velocity, With<Projectile>>();
+        let velocity = projectiles.single(app.world()).0;
+        assert!(velocity.abs_diff_eq(Vec2::new(110., 0.), 1e-3));
+    }
+}
@@ -0,0 +1,143 @@
+//! Weapons firing projectiles, homing or not.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use super::super::allegience::prelude::*;
+use super::super::kinematic::prelude::*;
+use super::super::pool::prelude::*;
+use super::super::unit::prelude::{Dead, Stunned};
+use super::bundles::*;
+use super::components::*;
+use super::homing::prelude::*;
+
+pub mod components {
+    use super::*;
+
+    /// Prerequisite: [`Position`]
+    #[derive(Debug, Clone, PartialEq, Component)]
+    pub struct Weapon {
+        /// Template of the fired projectiles.
+        pub projectile: Projectile,
+        /// Launch speed, and top speed of homing projectiles.
+        pub speed: f32,
+        pub lifetime: Duration,
+        pub cooldown: Timer,
+        /// Fire homing missiles instead of straight projectiles.
+        pub homing: Option<Homing>,
+    }
+    impl Weapon {
+        /// A weapon ready to fire.
+        pub fn new(
+            projectile: Projectile,
+            speed: f32,
+            lifetime: Duration,
+            cooldown: Duration,
+        ) -> Self {
+            let mut cooldown = Timer::new(cooldown, TimerMode::Once);
+            cooldown.tick(cooldown.duration());
+            Self {
+                projectile,
+                speed,
+                lifetime,
+                cooldown,
+                homing: None,
+            }
+        }
+        pub fn with_homing(mut self, homing: Homing) -> Self {
+            self.homing = Some(homing);
+            self
+        }
+
+        pub fn is_ready(&self) -> bool {
+            self.cooldown.finished()
+        }
+        /// Distance straight projectiles travel before they expire.
+        pub fn range(&self) -> f32 {
+            self.speed * self.lifetime.as_secs_f32()
+        }
+    }
+}
+
+pub mod events {
+    use super::*;
+
+    /// Fire a weapon towards a point, if it is ready.
+    #[derive(Debug, Event)]
+    pub struct FireWeapon {
+        pub shooter: Entity,
+        pub aim: Vec2,
+        /// Target of homing projectiles.
+        pub target: Option<Entity>,
+    }
+}
+
+pub(super) mod systems {
+    use super::*;
+    use components::*;
+    use events::*;
+
+    /// Cooldowns of the dead are frozen until they respawn.
+    pub fn tick_down_weapon_cooldown(
+        mut query: Query<&mut Weapon, Without<Dead>>,
+        time: Res<Time>,
+    ) {
+        for mut weapon in query.iter_mut() {
+            weapon.cooldown.tick(time.delta());
+        }
+    }
+
+    /// Projectiles inherit the shooter's [`Velocity`], e.g. that of a moving turret.
+    /// Stunned and dead shooters don't fire.
+    #[allow(clippy::type_complexity)]
+    pub fn fire_weapons(
+        mut commands: Commands,
+        mut events: EventReader<FireWeapon>,
+        mut shooters: Query<
+            (&Position, Option<&Velocity>, Option<&Faction>, &mut Weapon),
+            (Without<Stunned>, Without<Dead>),
+        >,
+        mut pool: ResMut<Pool<Projectile>>,
+    ) {
+        for &FireWeapon {
+            shooter,
+            aim,
+            target,
+        } in events.read()
+        {
+            let Ok((pos, vel, faction, mut weapon)) = shooters.get_mut(shooter) else {
+                continue;
+            };
+            if !weapon.is_ready() {
+                continue;
+            }
+            weapon.cooldown.reset();
+
+            let dir = (aim - pos.0).normalize_or(Vec2::X);
+            let mut projectile = pool.spawn(
+                &mut commands,
+                ProjectileBundle::new(
+                    weapon.projectile.clone().with_owner(shooter),
+                    pos.0,
+                    dir * weapon.speed + vel.map_or(Vec2::ZERO, |vel| vel.0),
+                    weapon.lifetime,
+                ),
+            );
+            if let Some(&faction) = faction {
+                projectile.insert(faction);
+            }
+            if let Some(homing) = weapon.homing {
+                projectile.insert(HomingBundle::new(homing, dir, weapon.speed));
+                if let Some(target) = target {
+                    projectile.insert((MovingTo { dest: aim }, homing.following(target)));
+                }
+            }
+        }
+    }
+}
+
+pub mod prelude {
+    pub use super::components::*;
+    pub use super::events::*;
+}
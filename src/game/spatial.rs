@@ -0,0 +1,277 @@
+use bevy::{prelude::*, utils::HashMap};
+
+use super::kinematic::prelude::*;
+use super::unit::prelude::*;
+
+/// Fraction of the segment from `start` to `end` at which it first touches a circle,
+/// or `None` if it misses. A segment starting inside the circle touches it at 0.
+pub fn segment_circle_toi(start: Vec2, end: Vec2, center: Vec2, radius: f32) -> Option<f32> {
+    let offset = start - center;
+    let c = offset.length_squared() - radius * radius;
+    if c <= 0. {
+        return Some(0.);
+    }
+
+    let delta = end - start;
+    let a = delta.length_squared();
+    if a == 0. {
+        return None;
+    }
+    let b = offset.dot(delta);
+    let discriminant = b * b - a * c;
+    if b >= 0. || discriminant < 0. {
+        return None;
+    }
+
+    let t = (-b - discriminant.sqrt()) / a;
+    (t <= 1.).then_some(t)
+}
+
+pub mod resources {
+    use super::*;
+
+    /// A uniform grid of units by their [`Position`] and [`Radius`], rebuilt every frame.
+    /// Dead and [`Airborne`] units are left out.
+    /// Units are also added and removed as they spawn and despawn.
+    #[derive(Debug, Clone, Resource)]
+    pub struct SpatialIndex {
+        cell_size: f32,
+        cells: HashMap<IVec2, Vec<(Entity, Vec2, f32)>>,
+        located: HashMap<Entity, IVec2>,
+        /// Largest radius in the index, by which queries are widened.
+        max_radius: f32,
+    }
+    impl Default for SpatialIndex {
+        fn default() -> Self {
+            Self::new(64.)
+        }
+    }
+    impl SpatialIndex {
+        pub fn new(cell_size: f32) -> Self {
+            Self {
+                cell_size,
+                cells: HashMap::new(),
+                located: HashMap::new(),
+                max_radius: 0.,
+            }
+        }
+
+        fn cell(&self, point: Vec2) -> IVec2 {
+            (point / self.cell_size).floor().as_ivec2()
+        }
+
+        /// Cells left empty since the last clear are dropped,
+        /// the others are kept for their allocations.
+        pub fn clear(&mut self) {
+            self.cells.retain(|_, entries| !entries.is_empty());
+            self.cells.values_mut().for_each(Vec::clear);
+            self.located.clear();
+            self.max_radius = 0.;
+        }
+
+        /// Insert an entity, replacing its previous entry.
+        pub fn insert(&mut self, entity: Entity, pos: Vec2, radius: f32) {
+            self.remove(entity);
+            let cell = self.cell(pos);
+            self.cells
+                .entry(cell)
+                .or_default()
+                .push((entity, pos, radius));
+            self.located.insert(entity, cell);
+            self.max_radius = self.max_radius.max(radius);
+        }
+
+        pub fn remove(&mut self, entity: Entity) {
+            let Some(cell) = self.located.remove(&entity) else {
+                return;
+            };
+            if let Some(entries) = self.cells.get_mut(&cell) {
+                entries.retain(|&(other, _, _)| other != entity);
+            }
+        }
+
+        pub fn contains(&self, entity: Entity) -> bool {
+            self.located.contains_key(&entity)
+        }
+
+        /// Entries with their center in any cell overlapping the box from `min` to `max`.
+        fn candidates(
+            &self,
+            min: Vec2,
+            max: Vec2,
+        ) -> impl Iterator<Item = (Entity, Vec2, f32)> + '_ {
+            let (min, max) = (self.cell(min), self.cell(max));
+            (min.x..=max.x)
+                .flat_map(move |x| (min.y..=max.y).map(move |y| IVec2::new(x, y)))
+                .filter_map(|cell| self.cells.get(&cell))
+                .flatten()
+                .copied()
+        }
+
+        /// Entities whose circle overlaps the circle around `center`.
+        pub fn query_circle(
+            &self,
+            center: Vec2,
+            radius: f32,
+        ) -> impl Iterator<Item = (Entity, Vec2, f32)> + '_ {
+            let reach = Vec2::splat(radius + self.max_radius);
+            self.candidates(center - reach, center + reach)
+                .filter(move |(_, pos, r)| pos.distance_squared(center) <= (radius + r).powi(2))
+        }
+
+        /// Entities touched by a circle of `radius` swept from `start` to `end`,
+        /// with the fraction of the sweep at which they are first touched, earliest first.
+        pub fn query_sweep(&self, start: Vec2, end: Vec2, radius: f32) -> Vec<(Entity, f32)> {
+            let reach = Vec2::splat(radius + self.max_radius);
+            let mut hits: Vec<_> = self
+                .candidates(start.min(end) - reach, start.max(end) + reach)
+                .filter_map(|(entity, pos, r)| {
+                    segment_circle_toi(start, end, pos, radius + r).map(|t| (entity, t))
+                })
+                .collect();
+            hits.sort_by(|a, b| a.1.total_cmp(&b.1));
+            hits
+        }
+    }
+}
+
+pub mod systems {
+    use super::*;
+    use resources::*;
+
+    #[allow(clippy::type_complexity)]
+    pub fn rebuild_spatial_index(
+        mut index: ResMut<SpatialIndex>,
+        query: Query<(Entity, &Position, &Radius), (With<Unit>, Without<Dead>, Without<Airborne>)>,
+    ) {
+        index.clear();
+        for (entity, pos, radius) in query.iter() {
+            index.insert(entity, pos.0, radius.0);
+        }
+    }
+
+    /// Index units as they spawn, so they can be found before the next rebuild.
+    #[allow(clippy::type_complexity)]
+    pub fn index_spawned_unit(
+        trigger: Trigger<OnAdd, Unit>,
+        query: Query<(&Position, &Radius), (Without<Dead>, Without<Airborne>)>,
+        mut index: ResMut<SpatialIndex>,
+    ) {
+        let entity = trigger.entity();
+        if let Ok((pos, radius)) = query.get(entity) {
+            index.insert(entity, pos.0, radius.0);
+        }
+    }
+
+    pub fn unindex_removed_unit(trigger: Trigger<OnRemove, Unit>, mut index: ResMut<SpatialIndex>) {
+        index.remove(trigger.entity());
+    }
+}
+
+/// Rebuilt once movement is resolved, for the systems checking overlaps before [`KinematicSet::Sync`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, SystemSet)]
+pub struct SpatialSet;
+
+pub struct SpatialPlugin;
+
+impl Plugin for SpatialPlugin {
+    fn build(&self, app: &mut App) {
+        use resources::*;
+        use systems::*;
+
+        app.init_resource::<SpatialIndex>()
+            .observe(index_spawned_unit)
+            .observe(unindex_removed_unit)
+            .add_systems(
+                Update,
+                rebuild_spatial_index
+                    .in_set(SpatialSet)
+                    .after(KinematicSet::Constraints)
+                    .before(KinematicSet::Sync),
+            );
+    }
+}
+
+pub mod prelude {
+    pub use super::resources::*;
+
+    pub use super::segment_circle_toi;
+    pub use super::{SpatialPlugin, SpatialSet};
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    use resources::*;
+
+    #[test]
+    fn segment_against_circle() {
+        let center = Vec2::new(50., 1.);
+
+        let t = segment_circle_toi(Vec2::ZERO, Vec2::new(100., 0.), center, 2.).unwrap();
+        assert!((t - (50. - 3f32.sqrt()) / 100.).abs() < 1e-5);
+        // Too short, passing beside or moving away.
+        assert_eq!(
+            segment_circle_toi(Vec2::ZERO, Vec2::new(40., 0.), center, 2.),
+            None
+        );
+        assert_eq!(
+            segment_circle_toi(Vec2::ZERO, Vec2::new(100., 10.), center, 2.),
+            None
+        );
+        assert_eq!(
+            segment_circle_toi(Vec2::new(60., 0.), Vec2::new(100., 0.), center, 2.),
+            None
+        );
+        assert_eq!(
+            segment_circle_toi(Vec2::new(50., 0.), Vec2::new(100., 0.), center, 2.),
+            Some(0.)
+        );
+    }
+
+    #[test]
+    fn sweep_does_not_tunnel() {
+        let mut index = SpatialIndex::new(10.);
+        let (near, far, aside) = (
+            Entity::from_raw(0),
+            Entity::from_raw(1),
+            Entity::from_raw(2),
+        );
+        index.insert(far, Vec2::new(300., 0.), 2.);
+        index.insert(near, Vec2::new(150., 0.), 2.);
+        index.insert(aside, Vec2::new(150., 50.), 2.);
+
+        // Much further in one step than the cell size and the targets' size.
+        let hits = index.query_sweep(Vec2::ZERO, Vec2::new(400., 0.), 1.);
+        let hits: Vec<_> = hits.into_iter().map(|(entity, _)| entity).collect();
+        assert_eq!(hits, vec![near, far]);
+
+        let around: Vec<_> = index.query_circle(Vec2::new(150., 45.), 5.).collect();
+        assert_eq!(around.len(), 1);
+        assert_eq!(around[0].0, aside);
+
+        index.remove(near);
+        assert!(!index.contains(near));
+        let hits = index.query_sweep(Vec2::ZERO, Vec2::new(400., 0.), 1.);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].0, far);
+    }
+
+    #[test]
+    fn airborne_units_left_out() {
+        let mut world = World::new();
+        world.init_resource::<SpatialIndex>();
+        let grounded = world.spawn((Unit, Position(Vec2::ZERO), Radius(5.))).id();
+        let flying = world
+            .spawn((Unit, Position(Vec2::ZERO), Radius(5.), Airborne))
+            .id();
+        world.run_system_once(systems::rebuild_spatial_index);
+
+        let index = world.resource::<SpatialIndex>();
+        assert!(index.contains(grounded));
+        assert!(!index.contains(flying));
+    }
+}
@@ -0,0 +1,495 @@
+//! Damage: layers of shields, armour and HP, knockback and hit events.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use super::super::allegience::prelude::*;
+use super::super::kinematic::prelude::*;
+use super::components::*;
+use super::death::components::{Dead, LastDamage};
+use super::events::*;
+use super::stats::components::{Stat, Stats};
+
+use components::{Absorbed, Armor, DamageKind};
+
+/// Damage left after flat armour, then percent armour.
+pub fn mitigate(amount: f32, kind: DamageKind, flat: f32, percent: f32) -> f32 {
+    if kind == DamageKind::True {
+        return amount;
+    }
+    ((amount - flat).max(0.) * (1. - percent)).max(0.)
+}
+
+/// Split a hit between a shield holding `shield`, then armour.
+/// Returns what those layers absorbed, and the damage left for HP.
+pub fn absorb(amount: f32, kind: DamageKind, shield: f32, armor: &Armor) -> (Absorbed, f32) {
+    let shield = amount.min(shield).max(0.);
+    let left = amount - shield;
+    let health = mitigate(left, kind, armor.flat(kind), armor.percent(kind));
+    (
+        Absorbed {
+            shield,
+            armor: left - health,
+        },
+        health,
+    )
+}
+
+pub mod components {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+    pub enum DamageKind {
+        #[default]
+        Physical,
+        Fire,
+        Cold,
+        Lightning,
+        Poison,
+        /// Ignores armour and resistances.
+        True,
+    }
+    impl DamageKind {
+        pub const ALL: [DamageKind; 6] = [
+            DamageKind::Physical,
+            DamageKind::Fire,
+            DamageKind::Cold,
+            DamageKind::Lightning,
+            DamageKind::Poison,
+            DamageKind::True,
+        ];
+    }
+
+    /// Reduction of damage per [`DamageKind`]: a flat amount, then a fraction of the rest.
+    /// Negative fractions are weaknesses.
+    #[derive(Debug, Clone, Copy, PartialEq, Default, Component)]
+    pub struct Armor {
+        flat: [f32; DamageKind::ALL.len()],
+        percent: [f32; DamageKind::ALL.len()],
+    }
+    impl Armor {
+        pub fn flat(&self, kind: DamageKind) -> f32 {
+            self.flat[kind as usize]
+        }
+        pub fn percent(&self, kind: DamageKind) -> f32 {
+            self.percent[kind as usize]
+        }
+        pub fn set_flat(&mut self, kind: DamageKind, flat: f32) {
+            self.flat[kind as usize] = flat;
+        }
+        pub fn set_percent(&mut self, kind: DamageKind, percent: f32) {
+            self.percent[kind as usize] = percent;
+        }
+        pub fn with_flat(mut self, kind: DamageKind, flat: f32) -> Self {
+            self.set_flat(kind, flat);
+            self
+        }
+        pub fn with_percent(mut self, kind: DamageKind, percent: f32) -> Self {
+            self.set_percent(kind, percent);
+            self
+        }
+    }
+
+    /// Absorbs damage before [`Armor`] and [`HP`].
+    /// Regenerates once no damage was taken for `delay`.
+    #[derive(Debug, Clone, PartialEq, Component)]
+    pub struct Shield {
+        pub value: f32,
+        pub max: f32,
+        /// Regeneration per second.
+        pub regen: f32,
+        pub delay: Timer,
+    }
+    impl Shield {
+        pub fn new(max: f32, regen: f32, delay: Duration) -> Self {
+            let mut delay = Timer::new(delay, TimerMode::Once);
+            delay.tick(delay.duration());
+            Self {
+                value: max,
+                max,
+                regen,
+                delay,
+            }
+        }
+        pub fn refill(&mut self) {
+            self.value = self.max;
+        }
+    }
+
+    /// HP regenerated per second, while alive.
+    #[derive(Debug, Clone, Copy, PartialEq, Default, Component)]
+    pub struct HealthRegen(pub f32);
+
+    /// How much of a hit each layer absorbed before it reached [`HP`].
+    #[derive(Debug, Clone, Copy, PartialEq, Default)]
+    pub struct Absorbed {
+        pub shield: f32,
+        /// Negative for weaknesses.
+        pub armor: f32,
+    }
+}
+
+pub mod resources {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Resource)]
+    pub struct DamageRules {
+        /// Whether allies of the source's faction can be damaged.
+        pub friendly_fire: bool,
+        /// Whether the source can damage itself, e.g. with explosions.
+        pub self_damage: bool,
+    }
+    impl Default for DamageRules {
+        fn default() -> Self {
+            Self {
+                friendly_fire: false,
+                self_damage: true,
+            }
+        }
+    }
+}
+
+pub mod events {
+    use super::*;
+
+    use components::*;
+
+    /// Request to damage a unit, going through invulnerability, faction rules and mitigation.
+    ///
+    /// What the rules need of the source is carried along,
+    /// since the source may be gone by the time the damage is applied.
+    #[derive(Debug, Clone, Copy, PartialEq, Event)]
+    pub struct DealDamage {
+        pub source: Option<Entity>,
+        /// Faction of the source when the damage was dealt.
+        pub source_faction: Option<Faction>,
+        /// Dealt from the ground, out of reach of [`Airborne`] targets.
+        pub grounded: bool,
+        pub target: Entity,
+        pub amount: f32,
+        pub kind: DamageKind,
+        /// Impulse applied to the target if it is hit.
+        pub knockback: Vec2,
+    }
+    impl DealDamage {
+        pub fn new(target: Entity, amount: f32) -> Self {
+            Self {
+                source: None,
+                source_faction: None,
+                grounded: false,
+                target,
+                amount,
+                kind: DamageKind::default(),
+                knockback: Vec2::ZERO,
+            }
+        }
+        pub fn from(mut self, source: Entity, faction: Option<Faction>) -> Self {
+            self.source = Some(source);
+            self.source_faction = faction;
+            self
+        }
+        pub fn grounded(mut self) -> Self {
+            self.grounded = true;
+            self
+        }
+        pub fn of_kind(mut self, kind: DamageKind) -> Self {
+            self.kind = kind;
+            self
+        }
+        pub fn with_knockback(mut self, knockback: Vec2) -> Self {
+            self.knockback = knockback;
+            self
+        }
+    }
+}
+
+pub(super) mod systems {
+    use super::*;
+    use components::*;
+    use events::DealDamage;
+    use resources::*;
+
+    #[allow(clippy::type_complexity, clippy::too_many_arguments)]
+    pub fn apply_damage(
+        mut commands: Commands,
+        mut requests: EventReader<DealDamage>,
+        mut targets: Query<(
+            &mut HP,
+            Has<Invulnerability>,
+            Has<Airborne>,
+            Option<&Faction>,
+            Option<&mut Shield>,
+            Option<&Armor>,
+            Option<&LastDamage>,
+        )>,
+        sources: Query<&Stats>,
+        rules: Res<DamageRules>,
+        relationships: Res<FactionRelationships>,
+        mut was_hit: EventWriter<UnitWasHit>,
+        mut did_hit: EventWriter<UnitDidHit>,
+    ) {
+        for request in requests.read() {
+            let Ok((mut hp, invulnerable, airborne, faction, mut shield, armor, last_damage)) =
+                targets.get_mut(request.target)
+            else {
+                continue;
+            };
+            if hp.is_dead() || invulnerable {
+                continue;
+            }
+
+            if airborne && request.grounded {
+                continue;
+            }
+            if let Some(source) = request.source {
+                if source == request.target {
+                    if !rules.self_damage {
+                        continue;
+                    }
+                } else if !rules.friendly_fire
+                    && relationships.relationship_between(request.source_faction, faction.copied())
+                        == Relationship::Allied
+                {
+                    continue;
+                }
+            }
+
+            let multiplier = request
+                .source
+                .and_then(|source| sources.get(source).ok()?.get(Stat::Damage))
+                .unwrap_or(1.);
+            let (absorbed, amount) = absorb(
+                request.amount * multiplier,
+                request.kind,
+                shield.as_ref().map_or(0., |shield| shield.value),
+                armor.unwrap_or(&Armor::default()),
+            );
+            if let Some(shield) = shield.as_mut() {
+                shield.value -= absorbed.shield;
+                shield.delay.reset();
+            }
+            hp.damage(amount);
+            commands.entity(request.target).insert(LastDamage {
+                source: request.source,
+                kind: request.kind,
+                amount,
+                attacker: request
+                    .source
+                    .or(last_damage.and_then(|last_damage| last_damage.attacker)),
+            });
+
+            if request.knockback != Vec2::ZERO {
+                commands
+                    .entity(request.target)
+                    .apply_impulse(request.knockback);
+            }
+
+            was_hit.send(UnitWasHit {
+                entity: request.target,
+                source: request.source,
+                amount,
+                kind: request.kind,
+                absorbed,
+            });
+            if let Some(source) = request.source {
+                did_hit.send(UnitDidHit {
+                    entity: source,
+                    target: request.target,
+                    amount,
+                    kind: request.kind,
+                });
+            }
+        }
+    }
+
+    pub fn regenerate_shields(mut query: Query<&mut Shield, Without<Dead>>, time: Res<Time>) {
+        for mut shield in query.iter_mut() {
+            if shield.delay.tick(time.delta()).finished() && shield.value < shield.max {
+                shield.value = (shield.value + shield.regen * time.delta_seconds()).min(shield.max);
+            }
+        }
+    }
+
+    pub fn regenerate_health(
+        mut query: Query<(&mut HP, &HealthRegen), Without<Dead>>,
+        time: Res<Time>,
+    ) {
+        for (mut hp, regen) in query.iter_mut() {
+            // Full HP is left untouched, not to be flagged as changed.
+            if hp.value < hp.max {
+                hp.heal(regen.0 * time.delta_seconds());
+            }
+        }
+    }
+}
+
+pub mod prelude {
+    pub use super::components::*;
+    pub use super::events::*;
+    pub use super::resources::*;
+
+    pub use super::{absorb, mitigate};
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    use components::*;
+    use events::*;
+    use resources::*;
+
+    #[test]
+    fn armor_then_resistance() {
+        assert_eq!(mitigate(30., DamageKind::Physical, 10., 0.5), 10.);
+        assert_eq!(mitigate(30., DamageKind::Fire, 0., 0.5), 15.);
+        // Never healing, and weaknesses amplify.
+        assert_eq!(mitigate(5., DamageKind::Physical, 10., 0.), 0.);
+        assert_eq!(mitigate(10., DamageKind::Cold, 0., -0.5), 15.);
+        assert_eq!(mitigate(10., DamageKind::True, 100., 1.), 10.);
+    }
+
+    #[test]
+    fn shield_then_armor() {
+        let armor = Armor::default()
+            .with_flat(DamageKind::Physical, 5.)
+            .with_percent(DamageKind::Fire, 0.5);
+        assert_eq!(
+            absorb(30., DamageKind::Physical, 10., &armor),
+            (
+                Absorbed {
+                    shield: 10.,
+                    armor: 5.
+                },
+                15.
+            )
+        );
+        // Armour is per damage kind.
+        assert_eq!(absorb(30., DamageKind::Fire, 0., &armor).1, 15.);
+        assert_eq!(absorb(30., DamageKind::Cold, 40., &armor).1, 0.);
+        assert_eq!(absorb(30., DamageKind::True, 0., &armor).1, 30.);
+    }
+
+    fn world() -> World {
+        let mut world = World::new();
+        world.init_resource::<Events<DealDamage>>();
+        world.init_resource::<Events<UnitWasHit>>();
+        world.init_resource::<Events<UnitDidHit>>();
+        world.init_resource::<DamageRules>();
+        world.insert_resource(FactionRelationships::with_default(Relationship::Hostile));
+        world
+    }
+
+    fn hp(world: &World, entity: Entity) -> f32 {
+        world.get::<HP>(entity).unwrap().value
+    }
+
+    #[test]
+    fn airborne_out_of_reach_from_the_ground() {
+        let mut world = world();
+        let grounded = world.spawn(HP::new(10., 10.)).id();
+        let flying = world.spawn((HP::new(10., 10.), Airborne)).id();
+        world.send_event(DealDamage::new(flying, 1.).from(grounded, None).grounded());
+        world.send_event(DealDamage::new(grounded, 1.).from(flying, None));
+        world.send_event(DealDamage::new(flying, 1.));
+        world.run_system_once(systems::apply_damage);
+
+        // Hit by the flier, and by damage without a source, but not from the ground.
+        assert_eq!(hp(&world, grounded), 9.);
+        assert_eq!(hp(&world, flying), 9.);
+    }
+
+    #[test]
+    fn invulnerable_units_take_no_damage() {
+        let mut world = world();
+        let unit = world.spawn((HP::new(10., 10.), Invulnerability)).id();
+        world.send_event(DealDamage::new(unit, 5.).of_kind(DamageKind::True));
+        world.run_system_once(systems::apply_damage);
+
+        assert_eq!(hp(&world, unit), 10.);
+        assert!(world.resource::<Events<UnitWasHit>>().is_empty());
+    }
+
+    #[test]
+    fn friendly_fire_and_self_damage_rules() {
+        let mut world = world();
+        let shooter = world.spawn((HP::new(10., 10.), Faction::A)).id();
+        let ally = world.spawn((HP::new(10., 10.), Faction::A)).id();
+        let enemy = world.spawn((HP::new(10., 10.), Faction::B)).id();
+        let mut volley = |world: &mut World, rules: DamageRules| {
+            world.insert_resource(rules);
+            for target in [shooter, ally, enemy] {
+                world.send_event(DealDamage::new(target, 1.).from(shooter, Some(Faction::A)));
+            }
+            world.run_system_once(systems::apply_damage);
+            world.resource_mut::<Events<DealDamage>>().clear();
+            [shooter, ally, enemy].map(|unit| hp(world, unit))
+        };
+
+        assert_eq!(volley(&mut world, DamageRules::default()), [9., 10., 9.]);
+        let rules = DamageRules {
+            friendly_fire: true,
+            self_damage: false,
+        };
+        assert_eq!(volley(&mut world, rules), [9., 9., 8.]);
+    }
+
+    #[test]
+    fn despawned_sources_keep_their_faction() {
+        let mut world = world();
+        let shooter = world.spawn((HP::new(10., 10.), Faction::A)).id();
+        let ally = world.spawn((HP::new(10., 10.), Faction::A, Airborne)).id();
+        let enemy = world.spawn((HP::new(10., 10.), Faction::B, Airborne)).id();
+        world.despawn(shooter);
+        for target in [ally, enemy] {
+            world.send_event(
+                DealDamage::new(target, 1.)
+                    .from(shooter, Some(Faction::A))
+                    .grounded(),
+            );
+        }
+        world.send_event(DealDamage::new(enemy, 1.).from(shooter, Some(Faction::A)));
+        world.run_system_once(systems::apply_damage);
+
+        // Neither its allies nor fliers are hit from the ground once it is gone.
+        assert_eq!(hp(&world, ally), 10.);
+        assert_eq!(hp(&world, enemy), 9.);
+    }
+
+    #[test]
+    fn shields_regenerate_once_not_hit_for_the_delay() {
+        let mut world = world();
+        world.init_resource::<Time>();
+        let unit = world
+            .spawn((
+                HP::new(5., 10.),
+                HealthRegen(1.),
+                Shield::new(10., 2., Duration::from_secs(2)),
+            ))
+            .id();
+        let mut frame = |world: &mut World| {
+            world
+                .resource_mut::<Time>()
+                .advance_by(Duration::from_secs(1));
+            world.run_system_once(systems::regenerate_shields);
+            world.run_system_once(systems::regenerate_health);
+            world.run_system_once(systems::apply_damage);
+            world.resource_mut::<Events<DealDamage>>().clear();
+            (hp(world, unit), world.get::<Shield>(unit).unwrap().value)
+        };
+
+        world.send_event(DealDamage::new(unit, 8.));
+        assert_eq!(frame(&mut world), (6., 2.));
+        // Health keeps regenerating, the shield waits for the delay.
+        assert_eq!(frame(&mut world), (7., 2.));
+        // Regenerating once the delay is over, until hit again.
+        world.send_event(DealDamage::new(unit, 1.));
+        assert_eq!(frame(&mut world), (8., 3.));
+        assert_eq!(frame(&mut world), (9., 3.));
+        assert_eq!(frame(&mut world), (10., 5.));
+        assert_eq!(frame(&mut world), (10., 7.));
+    }
+}
@@ -0,0 +1,349 @@
+//! Death: the `Dead` state, an optional fading corpse, then despawn
+//! unless the unit [`Respawn`]s.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use super::super::kinematic::prelude::*;
+use super::components::*;
+use super::damage::components::DamageKind;
+use super::events::*;
+use super::respawn::components::Respawn;
+
+pub mod components {
+    use super::*;
+
+    /// Tags a unit that died. It no longer moves by itself, fires, nor can be hit,
+    /// and those [`Following`] it lose it.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Component)]
+    pub struct Dead;
+
+    /// The latest damage a unit took.
+    #[derive(Debug, Clone, Copy, PartialEq, Component)]
+    pub struct LastDamage {
+        pub source: Option<Entity>,
+        pub kind: DamageKind,
+        pub amount: f32,
+        /// Latest entity that dealt damage, even if the latest damage had no source.
+        pub attacker: Option<Entity>,
+    }
+
+    /// A dead unit fading out before it is despawned.
+    #[derive(Debug, Clone, PartialEq, Eq, Component)]
+    pub struct Corpse {
+        pub fade: Timer,
+    }
+}
+
+pub mod resources {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Resource)]
+    pub struct DeathRules {
+        /// How long corpses fade out before being despawned.
+        /// `None` despawns dead units right away.
+        pub corpse_duration: Option<Duration>,
+    }
+    impl Default for DeathRules {
+        fn default() -> Self {
+            Self {
+                corpse_duration: Some(Duration::from_secs(3)),
+            }
+        }
+    }
+}
+
+pub mod events {
+    use super::*;
+
+    /// A dead unit is about to be despawned, e.g. to drop loot or play effects.
+    #[derive(Debug, Event)]
+    pub struct CorpseDespawned {
+        pub entity: Entity,
+        pub pos: Vec2,
+    }
+}
+
+pub(super) mod systems {
+    use super::*;
+    use components::*;
+    use events::*;
+    use resources::*;
+
+    /// Mark units as [`Dead`] the moment their HP runs out, sending and triggering a single [`UnitDied`].
+    #[allow(clippy::type_complexity)]
+    pub fn trigger_unit_died_event(
+        mut commands: Commands,
+        query: Query<(Entity, &HP, &Position, Option<&LastDamage>), (Changed<HP>, Without<Dead>)>,
+        mut events: EventWriter<UnitDied>,
+    ) {
+        for (entity, hp, pos, last_damage) in query.iter() {
+            if !hp.is_dead() {
+                continue;
+            }
+            commands.entity(entity).insert(Dead);
+            let died = UnitDied {
+                entity,
+                pos: pos.0,
+                killer: last_damage.and_then(|last_damage| last_damage.attacker),
+                last_damage: last_damage.copied(),
+            };
+            events.send(died);
+            commands.trigger_targets(died, entity);
+        }
+    }
+
+    /// Strip self-movement from dead units, and start their corpse phase.
+    #[allow(clippy::type_complexity)]
+    pub fn handle_dead_units(
+        mut commands: Commands,
+        query: Query<
+            (
+                Entity,
+                &Position,
+                Option<&Handle<ColorMaterial>>,
+                Has<Respawn>,
+            ),
+            Added<Dead>,
+        >,
+        mut materials: ResMut<Assets<ColorMaterial>>,
+        rules: Res<DeathRules>,
+        mut events: EventWriter<CorpseDespawned>,
+    ) {
+        for (entity, pos, material, respawns) in query.iter() {
+            let mut entity_commands = commands.entity(entity);
+            entity_commands.remove::<(
+                SelfMoving,
+                MovingTo,
+                MovingIn,
+                Following,
+                Decelerating,
+                Sprinting,
+            )>();
+
+            let Some(duration) = rules.corpse_duration else {
+                if respawns {
+                    entity_commands.insert(Visibility::Hidden);
+                } else {
+                    events.send(CorpseDespawned { entity, pos: pos.0 });
+                    entity_commands.despawn();
+                }
+                continue;
+            };
+            entity_commands.insert(Corpse {
+                fade: Timer::new(duration, TimerMode::Once),
+            });
+            // Fade a copy, so the material can be shared with the living.
+            if let Some(copy) = material
+                .and_then(|material| materials.get(material))
+                .cloned()
+            {
+                entity_commands.insert(materials.add(copy));
+            }
+        }
+    }
+
+    /// Followers of units that just died lose them, as if they were despawned.
+    pub fn lose_dead_targets(
+        mut commands: Commands,
+        mut followers: Query<(Entity, &mut MovingTo, &Following)>,
+        dead: Query<(), Added<Dead>>,
+        mut events: EventWriter<TargetLost>,
+    ) {
+        if dead.is_empty() {
+            return;
+        }
+        for (entity, mut moving_to, following) in followers.iter_mut() {
+            if !dead.contains(following.target) {
+                continue;
+            }
+            let last_known = following.lose(&mut commands.entity(entity), &mut moving_to);
+            events.send(TargetLost {
+                entity,
+                target: following.target,
+                last_known,
+            });
+        }
+    }
+
+    #[allow(clippy::type_complexity)]
+    pub fn fade_corpses(
+        mut commands: Commands,
+        mut query: Query<(
+            Entity,
+            &Position,
+            &mut Corpse,
+            Option<&Handle<ColorMaterial>>,
+            Has<Respawn>,
+        )>,
+        mut materials: ResMut<Assets<ColorMaterial>>,
+        mut events: EventWriter<CorpseDespawned>,
+        time: Res<Time>,
+    ) {
+        for (entity, pos, mut corpse, material, respawns) in query.iter_mut() {
+            if corpse.fade.tick(time.delta()).finished() {
+                if respawns {
+                    commands
+                        .entity(entity)
+                        .remove::<Corpse>()
+                        .insert(Visibility::Hidden);
+                } else {
+                    events.send(CorpseDespawned { entity, pos: pos.0 });
+                    commands.entity(entity).despawn();
+                }
+                continue;
+            }
+            if let Some(material) = material.and_then(|material| materials.get_mut(material)) {
+                material.color.set_alpha(corpse.fade.fraction_remaining());
+            }
+        }
+    }
+}
+
+pub mod prelude {
+    pub use super::components::*;
+    pub use super::events::*;
+    pub use super::resources::*;
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::super::super::allegience::prelude::*;
+    use super::super::damage::{events::DealDamage, resources::DamageRules, systems::apply_damage};
+    use super::*;
+
+    use components::*;
+    use events::*;
+    use resources::*;
+
+    fn world() -> World {
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world.init_resource::<Assets<ColorMaterial>>();
+        world.init_resource::<DamageRules>();
+        world.init_resource::<DeathRules>();
+        world.insert_resource(FactionRelationships::with_default(Relationship::Hostile));
+        world.init_resource::<Events<DealDamage>>();
+        world.init_resource::<Events<UnitWasHit>>();
+        world.init_resource::<Events<UnitDidHit>>();
+        world.init_resource::<Events<UnitDied>>();
+        world.init_resource::<Events<CorpseDespawned>>();
+        world.init_resource::<Events<TargetLost>>();
+        world
+    }
+
+    fn frame(world: &mut World) {
+        world.run_system_once(apply_damage);
+        world.run_system_once(systems::trigger_unit_died_event);
+        world.run_system_once(systems::handle_dead_units);
+        world.run_system_once(systems::lose_dead_targets);
+        world.run_system_once(systems::fade_corpses);
+    }
+
+    #[test]
+    fn dies_once() {
+        let mut world = world();
+        let killer = world.spawn_empty().id();
+        let unit = world
+            .spawn((
+                Position(Vec2::new(10., 0.)),
+                HP::new(10., 10.),
+                SelfMoving { accel: 100. },
+                MovingIn { dir: Vec2::X },
+            ))
+            .id();
+
+        // Lethal damage, then more damage over the next frames.
+        for _ in 0..3 {
+            world.send_event(DealDamage::new(unit, 6.).from(killer, None));
+            frame(&mut world);
+        }
+        // Changing the HP of the dead directly doesn't kill them again either.
+        world.get_mut::<HP>(unit).unwrap().value = 0.;
+        frame(&mut world);
+
+        let died: Vec<_> = world.resource_mut::<Events<UnitDied>>().drain().collect();
+        assert_eq!(died.len(), 1);
+        assert_eq!(died[0].entity, unit);
+        assert_eq!(died[0].pos, Vec2::new(10., 0.));
+        assert_eq!(died[0].killer, Some(killer));
+        let last_damage = died[0].last_damage.unwrap();
+        assert_eq!(last_damage.source, Some(killer));
+        assert_eq!(last_damage.amount, 6.);
+
+        let unit = world.entity(unit);
+        assert!(unit.contains::<Dead>());
+        assert!(unit.contains::<Corpse>());
+        assert!(!unit.contains::<SelfMoving>());
+        assert!(!unit.contains::<MovingIn>());
+    }
+
+    #[test]
+    fn corpse_fades_then_despawns() {
+        let mut world = world();
+        let material = world
+            .resource_mut::<Assets<ColorMaterial>>()
+            .add(ColorMaterial::from(Color::WHITE));
+        let unit = world
+            .spawn((Position(Vec2::ZERO), HP::new(0., 10.), material.clone()))
+            .id();
+        frame(&mut world);
+        // Faded on its own copy of the material.
+        let copy = world.get::<Handle<ColorMaterial>>(unit).unwrap().clone();
+        assert_ne!(copy, material);
+
+        world
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs(1));
+        world.run_system_once(systems::fade_corpses);
+        let materials = world.resource::<Assets<ColorMaterial>>();
+        assert!((materials.get(&copy).unwrap().color.alpha() - 2. / 3.).abs() < 1e-5);
+        assert_eq!(materials.get(&material).unwrap().color.alpha(), 1.);
+
+        world
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs(2));
+        world.run_system_once(systems::fade_corpses);
+        assert!(world.get_entity(unit).is_none());
+        let despawned: Vec<_> = world
+            .resource_mut::<Events<CorpseDespawned>>()
+            .drain()
+            .map(|event| event.entity)
+            .collect();
+        assert_eq!(despawned, vec![unit]);
+    }
+
+    #[test]
+    fn followers_lose_the_dead() {
+        let mut world = world();
+        let unit = world
+            .spawn((Position(Vec2::new(50., 0.)), HP::new(10., 10.)))
+            .id();
+        let follower = world
+            .spawn((
+                MovingTo {
+                    dest: Vec2::new(40., 0.),
+                },
+                Following {
+                    last_known: Some(Vec2::new(50., 0.)),
+                    ..Following::new(unit)
+                },
+            ))
+            .id();
+
+        world.send_event(DealDamage::new(unit, 10.));
+        frame(&mut world);
+
+        assert!(world.get::<Following>(follower).is_none());
+        assert!(world.get::<Decelerating>(follower).is_some());
+        let lost: Vec<_> = world
+            .resource_mut::<Events<TargetLost>>()
+            .drain()
+            .map(|event| (event.entity, event.target, event.last_known))
+            .collect();
+        assert_eq!(lost, vec![(follower, unit, Vec2::new(50., 0.))]);
+    }
+}
@@ -3,9 +3,13 @@ use bevy::prelude::*;
 use super::allegience::prelude::*;
 use super::kinematic::prelude::*;
 
-pub mod components {
-    use std::time::Duration;
+pub mod damage;
+pub mod death;
+pub mod respawn;
+pub mod stats;
+pub mod status;
 
+pub mod components {
     use super::*;
 
     /// Tags an entity as a unit
@@ -34,6 +38,10 @@ pub mod components {
             self.value > 0.
         }
 
+        pub fn damage(&mut self, amount: f32) {
+            self.value = (self.value - amount).max(0.);
+        }
+
         pub fn heal(&mut self, amount: f32) {
             self.value = (self.value + amount).min(self.max);
         }
@@ -47,23 +55,16 @@ pub mod components {
     #[derive(Debug, Clone, Copy, PartialEq, Default, Component)]
     pub struct Radius(pub f32);
 
-    #[derive(Debug, Clone, PartialEq, Eq, Component)]
-    pub struct Invulnerability {
-        pub duration: Timer,
-    }
-
-    impl Invulnerability {
-        pub fn new(duration: Duration) -> Self {
-            Self {
-                duration: Timer::new(duration, TimerMode::Once),
-            }
-        }
-    }
-    impl Default for Invulnerability {
-        fn default() -> Self {
-            Self::new(Duration::ZERO)
-        }
-    }
+    /// Tags a unit that takes no damage.
+    /// Maintained from the invulnerable [`status`] effect, which should be applied instead.
+    ///
+    /// Breaking change: this used to hold a timer, and was on every unit with a zero duration.
+    /// Inserting it now makes a unit invulnerable until removed, hence no `Default`.
+    /// Timed invulnerability goes through [`ApplyStatus`](status::events::ApplyStatus)
+    /// with [`StatusEffect::invulnerable`](status::components::StatusEffect::invulnerable)
+    /// instead of `Invulnerability::new(duration)`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Component)]
+    pub struct Invulnerability;
 }
 
 pub mod bundles {
@@ -71,13 +72,15 @@ pub mod bundles {
 
     use components::*;
 
+    /// An unaligned unit, e.g. wildlife or a crate, drawn in gray.
+    /// Its relationships are the unaligned ones of [`FactionRelationships`].
     #[derive(Debug, Default, Bundle)]
     pub struct UnitBundleWithoutFaction {
         pub unit: Unit,
         pub hp: HP,
-        pub invulnerability: Invulnerability,
         pub radius: Radius,
         pub kinematic: SymmeticFullKinematic,
+        pub friction: Friction,
     }
     impl UnitBundleWithoutFaction {
         pub fn new(hp: HP, radius: Radius) -> Self {
@@ -108,70 +111,148 @@ pub mod bundles {
     }
 }
 
+pub mod resources {
+    use super::*;
+    use bevy::sprite::Mesh2dHandle;
+    use bevy::utils::HashMap;
+
+    /// Meshes and materials shared by units that look alike, so they are batched together.
+    #[derive(Debug, Default, Resource)]
+    pub struct UnitRenderCache {
+        /// By the bits of the radius.
+        meshes: HashMap<u32, Mesh2dHandle>,
+        materials: HashMap<Option<Faction>, Handle<ColorMaterial>>,
+    }
+    impl UnitRenderCache {
+        pub fn mesh(&mut self, radius: f32, meshes: &mut Assets<Mesh>) -> Mesh2dHandle {
+            self.meshes
+                .entry(radius.to_bits())
+                .or_insert_with(|| Mesh2dHandle(meshes.add(Circle { radius })))
+                .clone()
+        }
+        pub fn material(
+            &mut self,
+            faction: Option<Faction>,
+            materials: &mut Assets<ColorMaterial>,
+        ) -> Handle<ColorMaterial> {
+            self.materials
+                .entry(faction)
+                .or_insert_with(|| materials.add(Faction::color_opt(faction)))
+                .clone()
+        }
+    }
+}
+
 pub mod events {
     use super::*;
 
-    #[derive(Debug, Event)]
+    use damage::components::{Absorbed, DamageKind};
+    use death::components::LastDamage;
+
+    /// Sent as soon as a unit spawns, and triggered on it for observers.
+    #[derive(Debug, Clone, Copy, Event)]
     pub struct UnitSpawned(pub Entity);
 
+    /// Sent once per death, and triggered on the unit for observers.
+    #[derive(Debug, Clone, Copy, Event)]
+    pub struct UnitDied {
+        pub entity: Entity,
+        pub pos: Vec2,
+        /// Latest entity that damaged the unit.
+        pub killer: Option<Entity>,
+        pub last_damage: Option<LastDamage>,
+    }
+
+    /// A unit took damage, after mitigation.
     #[derive(Debug, Event)]
-    pub struct UnitDied(pub Entity);
+    pub struct UnitWasHit {
+        pub entity: Entity,
+        pub source: Option<Entity>,
+        pub amount: f32,
+        pub kind: DamageKind,
+        /// What the shield and armour took before HP.
+        pub absorbed: Absorbed,
+    }
 
-    // #[derive(Debug, Event)]
-    // pub struct UnitWasHit(pub Entity);
+    /// A unit was healed in one go, e.g. by a heal over time ticking.
+    /// Continuous regeneration isn't reported.
+    #[derive(Debug, Event)]
+    pub struct UnitHealed {
+        pub entity: Entity,
+        pub amount: f32,
+    }
 
-    // #[derive(Debug, Event)]
-    // pub struct UnitDidHit(pub Entity);
+    /// An entity dealt damage to a unit, after mitigation.
+    #[derive(Debug, Event)]
+    pub struct UnitDidHit {
+        pub entity: Entity,
+        pub target: Entity,
+        pub amount: f32,
+        pub kind: DamageKind,
+    }
 }
 
 pub mod systems {
     use super::*;
-    use bevy::sprite::{MaterialMesh2dBundle, Mesh2dHandle};
+    use bevy::sprite::MaterialMesh2dBundle;
 
     use components::*;
+    use death::components::Corpse;
     use events::*;
+    use resources::*;
 
-    pub fn tick_down_invulnerability(mut query: Query<&mut Invulnerability>, time: Res<Time>) {
-        for mut invuln in query.iter_mut() {
-            invuln.duration.tick(time.delta());
-        }
-    }
-
-    // TODO: May change this to an observer
-    pub fn add_sprite_to_units(
+    pub fn add_sprite_to_unit(
+        trigger: Trigger<OnAdd, Unit>,
         mut commands: Commands,
-        query: Query<(Entity, &Faction, &Position, &Radius), Added<Unit>>,
+        query: Query<(Option<&Faction>, &Position, &Radius)>,
+        mut cache: ResMut<UnitRenderCache>,
         mut meshes: ResMut<Assets<Mesh>>,
         mut materials: ResMut<Assets<ColorMaterial>>,
     ) {
-        for (entity, faction, pos, &Radius(radius)) in query.iter() {
-            commands.entity(entity).insert(MaterialMesh2dBundle {
-                mesh: Mesh2dHandle(meshes.add(Circle { radius })),
-                material: materials.add(faction.color()),
-                transform: Transform::from_translation(pos.0.extend(0.0)),
-                ..default()
-            });
-        }
+        let entity = trigger.entity();
+        let Ok((faction, pos, &Radius(radius))) = query.get(entity) else {
+            return;
+        };
+        commands.entity(entity).insert(MaterialMesh2dBundle {
+            mesh: cache.mesh(radius, &mut meshes),
+            material: cache.material(faction.copied(), &mut materials),
+            transform: Transform::from_translation(pos.0.extend(0.0)),
+            ..default()
+        });
     }
 
-    pub fn trigger_unit_spawned_event(
-        query: Query<Entity, Added<Unit>>,
-        mut events: EventWriter<UnitSpawned>,
+    /// Recolor units whose [`Faction`] changed. Corpses keep their own fading material.
+    #[allow(clippy::type_complexity)]
+    pub fn update_unit_material(
+        mut query: Query<
+            (&mut Handle<ColorMaterial>, Option<&Faction>),
+            (With<Unit>, Without<Corpse>),
+        >,
+        changed: Query<Entity, (With<Unit>, Changed<Faction>)>,
+        mut removed: RemovedComponents<Faction>,
+        mut cache: ResMut<UnitRenderCache>,
+        mut materials: ResMut<Assets<ColorMaterial>>,
     ) {
-        for entity in query.iter() {
-            events.send(UnitSpawned(entity));
+        for entity in changed.iter().chain(removed.read()) {
+            let Ok((mut material, faction)) = query.get_mut(entity) else {
+                continue;
+            };
+            let wanted = cache.material(faction.copied(), &mut materials);
+            if *material != wanted {
+                *material = wanted;
+            }
         }
     }
 
-    pub fn trigger_unit_died_event(
-        query: Query<(Entity, &HP), Changed<HP>>,
-        mut events: EventWriter<UnitDied>,
+    /// Send [`UnitSpawned`], both buffered and to observers of the unit.
+    pub fn trigger_unit_spawned(
+        trigger: Trigger<OnAdd, Unit>,
+        mut commands: Commands,
+        mut events: EventWriter<UnitSpawned>,
     ) {
-        for (entity, hp) in query.iter() {
-            if hp.is_dead() {
-                events.send(UnitDied(entity));
-            }
-        }
+        let entity = trigger.entity();
+        events.send(UnitSpawned(entity));
+        commands.trigger_targets(UnitSpawned(entity), entity);
     }
 }
 
@@ -179,22 +260,68 @@ pub struct UnitPlugin;
 
 impl Plugin for UnitPlugin {
     fn build(&self, app: &mut App) {
+        use damage::{events::*, resources::*, systems::*};
+        use death::{events::*, resources::*, systems::*};
         use events::*;
+        use resources::*;
+        use respawn::{events::*, resources::*, systems::*};
+        use stats::systems::*;
+        use status::{events::*, systems::*};
         use systems::*;
 
-        app.add_event::<UnitSpawned>()
+        app.init_resource::<UnitRenderCache>()
+            .init_resource::<DamageRules>()
+            .init_resource::<DeathRules>()
+            .init_resource::<RespawnRules>()
+            .add_event::<UnitSpawned>()
             .add_event::<UnitDied>()
-            .add_systems(Update, add_sprite_to_units)
-            .add_systems(Update, trigger_unit_spawned_event)
-            .add_systems(Update, trigger_unit_died_event)
-            .add_systems(Update, tick_down_invulnerability);
+            .add_event::<CorpseDespawned>()
+            .add_event::<UnitRespawned>()
+            .add_event::<DealDamage>()
+            .add_event::<UnitWasHit>()
+            .add_event::<UnitDidHit>()
+            .add_event::<UnitHealed>()
+            .add_event::<ApplyStatus>()
+            .add_event::<DispelStatus>()
+            .add_event::<StatusRemoved>()
+            .observe(add_sprite_to_unit)
+            .observe(add_stats_to_unit)
+            .observe(trigger_unit_spawned)
+            .add_systems(Update, update_unit_material)
+            .add_systems(
+                Update,
+                (
+                    apply_status_events,
+                    tick_status_effects,
+                    update_status_markers,
+                    drop_modifiers_of_despawned_sources,
+                    write_stats,
+                    regenerate_shields,
+                    regenerate_health,
+                    apply_damage,
+                    trigger_unit_died_event,
+                    start_respawning,
+                    clear_status_of_dead,
+                    handle_dead_units,
+                    lose_dead_targets,
+                    fade_corpses,
+                    respawn_units,
+                )
+                    .chain(),
+            );
     }
 }
 
 pub mod prelude {
     pub use super::bundles::*;
     pub use super::components::*;
+    pub use super::damage::prelude::*;
+    pub use super::death::prelude::*;
     pub use super::events::*;
+    pub use super::resources::*;
+    pub use super::respawn::prelude::*;
+    pub use super::stats::prelude::*;
+    pub use super::status::prelude::*;
 
     pub use super::UnitPlugin;
 }
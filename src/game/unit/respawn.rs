@@ -0,0 +1,371 @@
+//! Respawning dead units at spawn points of their faction.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use super::super::allegience::prelude::*;
+use super::super::kinematic::prelude::*;
+use super::components::*;
+use super::damage::components::Shield;
+use super::death::components::*;
+use super::resources::UnitRenderCache;
+use super::status::components::StatusEffect;
+use super::status::events::ApplyStatus;
+
+use resources::SpawnPlacement;
+
+/// Pick the spawn point, among `points`, for a unit that died at `death_pos`.
+/// Points with no hostile within `safe_radius` are preferred, if there are any.
+pub fn choose_spawn(
+    placement: SpawnPlacement,
+    points: &[Vec2],
+    death_pos: Vec2,
+    hostiles: &[Vec2],
+    safe_radius: f32,
+    rng: &mut impl Rng,
+) -> Option<Vec2> {
+    let threat_distance = |point: Vec2| {
+        hostiles
+            .iter()
+            .map(|hostile| hostile.distance(point))
+            .fold(f32::INFINITY, f32::min)
+    };
+    let safe: Vec<_> = points
+        .iter()
+        .copied()
+        .filter(|&point| threat_distance(point) >= safe_radius)
+        .collect();
+    let candidates = if safe.is_empty() { points } else { &safe };
+
+    match placement {
+        SpawnPlacement::Nearest => candidates.iter().copied().min_by(|a, b| {
+            a.distance_squared(death_pos)
+                .total_cmp(&b.distance_squared(death_pos))
+        }),
+        SpawnPlacement::Random => {
+            (!candidates.is_empty()).then(|| candidates[rng.gen_range(0..candidates.len())])
+        }
+        SpawnPlacement::FarthestFromHostiles => candidates
+            .iter()
+            .copied()
+            .max_by(|&a, &b| threat_distance(a).total_cmp(&threat_distance(b))),
+    }
+}
+
+pub mod components {
+    use super::*;
+
+    /// Where units of the given factions respawn.
+    /// Units without a faction may use any spawn point.
+    ///
+    /// Prerequisite: [`Position`]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Component)]
+    pub struct SpawnPoint {
+        pub factions: Factions,
+    }
+    impl SpawnPoint {
+        pub fn new(factions: Factions) -> Self {
+            Self { factions }
+        }
+        pub fn accepts(&self, faction: Option<Faction>) -> bool {
+            faction.is_none_or(|faction| self.factions.contains(faction.flag()))
+        }
+    }
+
+    /// Revive the unit at a spawn point some time after it dies, instead of despawning it.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Component)]
+    pub struct Respawn {
+        pub delay: Duration,
+        /// How long the unit is invulnerable after respawning.
+        pub invulnerability: Duration,
+    }
+
+    /// A dead unit waiting to respawn.
+    #[derive(Debug, Clone, PartialEq, Component)]
+    pub struct Respawning {
+        pub timer: Timer,
+        /// [`SelfMoving::accel`] to restore, as it is stripped from the dead.
+        pub(super) accel: Option<f32>,
+    }
+}
+
+pub mod resources {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub enum SpawnPlacement {
+        /// The spawn point nearest to where the unit died.
+        #[default]
+        Nearest,
+        Random,
+        FarthestFromHostiles,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Resource)]
+    pub struct RespawnRules {
+        pub placement: SpawnPlacement,
+        /// Distance from non-allied units at which a spawn point is safe.
+        pub safe_radius: f32,
+    }
+    impl Default for RespawnRules {
+        fn default() -> Self {
+            Self {
+                placement: SpawnPlacement::default(),
+                safe_radius: 150.,
+            }
+        }
+    }
+}
+
+pub mod events {
+    use super::*;
+
+    #[derive(Debug, Event)]
+    pub struct UnitRespawned {
+        pub entity: Entity,
+        pub pos: Vec2,
+    }
+}
+
+pub(super) mod systems {
+    use super::*;
+    use components::*;
+    use events::*;
+    use resources::*;
+
+    pub fn start_respawning(
+        mut commands: Commands,
+        query: Query<(Entity, &Respawn, Option<&SelfMoving>), Added<Dead>>,
+    ) {
+        for (entity, respawn, self_moving) in query.iter() {
+            commands.entity(entity).insert(Respawning {
+                timer: Timer::new(respawn.delay, TimerMode::Once),
+                accel: self_moving.map(|self_moving| self_moving.accel),
+            });
+        }
+    }
+
+    #[allow(clippy::type_complexity, clippy::too_many_arguments)]
+    pub fn respawn_units(
+        mut commands: Commands,
+        mut query: Query<
+            (
+                Entity,
+                &mut Respawning,
+                &Respawn,
+                &mut Position,
+                &mut Velocity,
+                &mut HP,
+                Option<&mut Shield>,
+                Option<&Faction>,
+                Option<&mut Handle<ColorMaterial>>,
+            ),
+            With<Dead>,
+        >,
+        points: Query<(&Position, &SpawnPoint), Without<Respawning>>,
+        living: Query<(&Position, Option<&Faction>), (With<Unit>, Without<Dead>)>,
+        relationships: Res<FactionRelationships>,
+        rules: Res<RespawnRules>,
+        mut cache: ResMut<UnitRenderCache>,
+        mut materials: ResMut<Assets<ColorMaterial>>,
+        mut events: EventWriter<UnitRespawned>,
+        mut statuses: EventWriter<ApplyStatus>,
+        time: Res<Time>,
+    ) {
+        for (
+            entity,
+            mut respawning,
+            respawn,
+            mut pos,
+            mut vel,
+            mut hp,
+            shield,
+            faction,
+            material,
+        ) in query.iter_mut()
+        {
+            if !respawning.timer.tick(time.delta()).finished() {
+                continue;
+            }
+
+            let faction = faction.copied();
+            let candidates: Vec<_> = points
+                .iter()
+                .filter(|(_, point)| point.accepts(faction))
+                .map(|(point_pos, _)| point_pos.0)
+                .collect();
+            let hostiles: Vec<_> = living
+                .iter()
+                .filter(|(_, other)| {
+                    relationships.relationship_between(faction, other.copied())
+                        != Relationship::Allied
+                })
+                .map(|(hostile_pos, _)| hostile_pos.0)
+                .collect();
+            // Without any spawn point, revive where the unit died.
+            pos.0 = choose_spawn(
+                rules.placement,
+                &candidates,
+                pos.0,
+                &hostiles,
+                rules.safe_radius,
+                &mut rand::thread_rng(),
+            )
+            .unwrap_or(pos.0);
+            vel.0 = Vec2::ZERO;
+            hp.refill();
+            if let Some(mut shield) = shield {
+                shield.refill();
+            }
+
+            // Back from the faded copy to the shared material.
+            if let Some(mut material) = material {
+                *material = cache.material(faction, &mut materials);
+            }
+            let mut entity_commands = commands.entity(entity);
+            entity_commands
+                .remove::<(Dead, Corpse, LastDamage, Respawning)>()
+                .insert(Visibility::Inherited);
+            if let Some(accel) = respawning.accel {
+                entity_commands.insert(SelfMoving { accel });
+            }
+            if !respawn.invulnerability.is_zero() {
+                statuses.send(ApplyStatus {
+                    target: entity,
+                    effect: StatusEffect::invulnerable(respawn.invulnerability),
+                });
+            }
+            events.send(UnitRespawned { entity, pos: pos.0 });
+        }
+    }
+}
+
+pub mod prelude {
+    pub use super::components::*;
+    pub use super::events::*;
+    pub use super::resources::*;
+
+    pub use super::choose_spawn;
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::super::status::components::StatusKind;
+    use super::*;
+
+    use components::*;
+    use events::*;
+    use resources::*;
+
+    #[test]
+    fn spawn_placement() {
+        let points = [Vec2::new(0., 0.), Vec2::new(100., 0.), Vec2::new(300., 0.)];
+        let hostiles = [Vec2::new(90., 0.)];
+        let rng = &mut rand::thread_rng();
+        let choose = |placement, rng: &mut _| {
+            choose_spawn(placement, &points, Vec2::new(110., 0.), &hostiles, 50., rng)
+        };
+
+        // The nearest point is next to a hostile, so the nearest safe one is taken instead.
+        assert_eq!(choose(SpawnPlacement::Nearest, rng), Some(points[0]));
+        assert_eq!(
+            choose(SpawnPlacement::FarthestFromHostiles, rng),
+            Some(points[2])
+        );
+        for _ in 0..20 {
+            assert_ne!(choose(SpawnPlacement::Random, rng), Some(points[1]));
+        }
+        assert_eq!(
+            choose_spawn(SpawnPlacement::Random, &[], Vec2::ZERO, &hostiles, 50., rng),
+            None
+        );
+    }
+
+    #[test]
+    fn respawned_alive_and_protected() {
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world.init_resource::<Assets<ColorMaterial>>();
+        world.init_resource::<UnitRenderCache>();
+        world.init_resource::<RespawnRules>();
+        world.insert_resource(FactionRelationships::with_default(Relationship::Hostile));
+        world.init_resource::<Events<UnitRespawned>>();
+        world.init_resource::<Events<ApplyStatus>>();
+
+        let point = Vec2::new(100., 0.);
+        world.spawn((Position(point), SpawnPoint::new(Factions::A)));
+        world.spawn((Position(Vec2::new(-100., 0.)), SpawnPoint::new(Factions::B)));
+        let faded = world
+            .resource_mut::<Assets<ColorMaterial>>()
+            .add(Color::BLACK);
+        let unit = world
+            .spawn((
+                Unit,
+                Faction::A,
+                Position(Vec2::ZERO),
+                Velocity(Vec2::X),
+                HP::new(0., 10.),
+                SelfMoving { accel: 50. },
+                faded.clone(),
+                Respawn {
+                    delay: Duration::from_secs(1),
+                    invulnerability: Duration::from_secs(2),
+                },
+                Dead,
+            ))
+            .id();
+        world.run_system_once(systems::start_respawning);
+        // Stripped from the dead.
+        world.entity_mut(unit).remove::<SelfMoving>();
+
+        world
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_millis(500));
+        world.run_system_once(systems::respawn_units);
+        assert!(world.get::<Dead>(unit).is_some());
+
+        world
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_millis(500));
+        world.run_system_once(systems::respawn_units);
+        let entity = world.entity(unit);
+        assert!(!entity.contains::<Dead>());
+        assert!(!entity.contains::<Respawning>());
+        assert_eq!(entity.get::<Position>().unwrap().0, point);
+        assert_eq!(entity.get::<Velocity>().unwrap().0, Vec2::ZERO);
+        assert_eq!(entity.get::<HP>().unwrap().value, 10.);
+        assert_eq!(entity.get::<SelfMoving>().unwrap().accel, 50.);
+        let material = entity.get::<Handle<ColorMaterial>>().unwrap().clone();
+        assert_ne!(material, faded);
+        world.resource_scope(|world, mut cache: Mut<UnitRenderCache>| {
+            let mut materials = world.resource_mut::<Assets<ColorMaterial>>();
+            assert_eq!(material, cache.material(Some(Faction::A), &mut materials));
+        });
+
+        let respawned: Vec<_> = world
+            .resource_mut::<Events<UnitRespawned>>()
+            .drain()
+            .map(|event| (event.entity, event.pos))
+            .collect();
+        assert_eq!(respawned, vec![(unit, point)]);
+        let statuses: Vec<_> = world
+            .resource_mut::<Events<ApplyStatus>>()
+            .drain()
+            .map(|event| {
+                (
+                    event.target,
+                    event.effect.kind,
+                    event.effect.duration.duration(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![(unit, StatusKind::Invulnerable, Duration::from_secs(2))]
+        );
+    }
+}
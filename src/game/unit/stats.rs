@@ -0,0 +1,383 @@
+//! Derived stats: base values with layers of modifiers, written back into the
+//! components they drive.
+
+use bevy::prelude::*;
+use bevy::sprite::Mesh2dHandle;
+
+use super::super::kinematic::prelude::*;
+use super::components::*;
+use super::resources::UnitRenderCache;
+
+use components::{ModifierLayer, StatModifier};
+
+/// `(base + flat) * (1 + percent) * multipliers`, over the modifiers of a single stat.
+pub fn stat_value<'a>(base: f32, modifiers: impl IntoIterator<Item = &'a StatModifier>) -> f32 {
+    let (mut flat, mut percent, mut multiplier) = (0., 0., 1.);
+    for modifier in modifiers {
+        match modifier.layer {
+            ModifierLayer::Flat => flat += modifier.value,
+            ModifierLayer::PercentAdd => percent += modifier.value,
+            ModifierLayer::Multiply => multiplier *= modifier.value,
+        }
+    }
+    (base + flat) * (1. + percent) * multiplier
+}
+
+pub mod components {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum Stat {
+        /// Written into [`HP::max`], keeping the fraction of HP left.
+        MaxHp,
+        /// Written into [`SelfMoving::accel`].
+        Accel,
+        /// Written into [`Radius`] and [`CrossSectionSize`].
+        Radius,
+        /// Written into [`Mass`].
+        Mass,
+        /// Multiplier of the damage dealt, 1 by default.
+        Damage,
+    }
+    impl Stat {
+        pub const ALL: [Stat; 5] = [
+            Stat::MaxHp,
+            Stat::Accel,
+            Stat::Radius,
+            Stat::Mass,
+            Stat::Damage,
+        ];
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub enum ModifierLayer {
+        /// Added to the base.
+        #[default]
+        Flat,
+        /// Summed with the other percentages, then applied once.
+        PercentAdd,
+        /// Applied one after the other.
+        Multiply,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct StatModifier {
+        pub stat: Stat,
+        pub layer: ModifierLayer,
+        pub value: f32,
+        /// What grants the modifier, e.g. an item, a caster or the unit itself.
+        pub source: Entity,
+        /// Tells apart several modifiers from the same source.
+        pub name: &'static str,
+    }
+    impl StatModifier {
+        pub fn new(stat: Stat, layer: ModifierLayer, value: f32, source: Entity) -> Self {
+            Self {
+                stat,
+                layer,
+                value,
+                source,
+                name: "",
+            }
+        }
+        pub fn flat(stat: Stat, value: f32, source: Entity) -> Self {
+            Self::new(stat, ModifierLayer::Flat, value, source)
+        }
+        pub fn percent(stat: Stat, value: f32, source: Entity) -> Self {
+            Self::new(stat, ModifierLayer::PercentAdd, value, source)
+        }
+        pub fn multiply(stat: Stat, value: f32, source: Entity) -> Self {
+            Self::new(stat, ModifierLayer::Multiply, value, source)
+        }
+        pub fn named(mut self, name: &'static str) -> Self {
+            self.name = name;
+            self
+        }
+
+        fn is(&self, stat: Stat, source: Entity, name: &str) -> bool {
+            self.stat == stat && self.source == source && self.name == name
+        }
+    }
+
+    /// The base values and modifiers of a unit's stats.
+    /// Final values are recomputed whenever they change, and then written into
+    /// the unit's components. Missing bases are taken from those components.
+    /// Modifiers are dropped once their source is despawned.
+    #[derive(Debug, Clone, PartialEq, Default, Component)]
+    pub struct Stats {
+        base: [Option<f32>; Stat::ALL.len()],
+        value: [Option<f32>; Stat::ALL.len()],
+        modifiers: Vec<StatModifier>,
+    }
+    impl Stats {
+        pub fn with_base(mut self, stat: Stat, base: f32) -> Self {
+            self.set_base(stat, base);
+            self
+        }
+        pub fn base(&self, stat: Stat) -> Option<f32> {
+            self.base[stat as usize]
+        }
+        /// Final value, or `None` without a base.
+        pub fn get(&self, stat: Stat) -> Option<f32> {
+            self.value[stat as usize]
+        }
+        pub fn modifiers(&self) -> &[StatModifier] {
+            &self.modifiers
+        }
+        pub fn modifier(&self, stat: Stat, source: Entity, name: &str) -> Option<&StatModifier> {
+            self.modifiers
+                .iter()
+                .find(|modifier| modifier.is(stat, source, name))
+        }
+
+        pub fn set_base(&mut self, stat: Stat, base: f32) {
+            self.base[stat as usize] = Some(base);
+            self.recompute(stat);
+        }
+        /// Add a modifier, replacing the one of the same stat, layer, source and name.
+        pub fn add(&mut self, modifier: StatModifier) {
+            match self.modifiers.iter_mut().find(|other| {
+                other.layer == modifier.layer
+                    && other.is(modifier.stat, modifier.source, modifier.name)
+            }) {
+                Some(other) => *other = modifier,
+                None => self.modifiers.push(modifier),
+            }
+            self.recompute(modifier.stat);
+        }
+        pub fn remove(&mut self, stat: Stat, source: Entity, name: &str) {
+            self.remove_where(|modifier| modifier.is(stat, source, name));
+        }
+        /// Remove every modifier granted by `source`, e.g. an unequipped item.
+        pub fn remove_source(&mut self, source: Entity) {
+            self.remove_where(|modifier| modifier.source == source);
+        }
+
+        pub(super) fn remove_where(&mut self, predicate: impl Fn(&StatModifier) -> bool) {
+            let mut changed = Vec::new();
+            self.modifiers.retain(|modifier| {
+                let remove = predicate(modifier);
+                if remove {
+                    changed.push(modifier.stat);
+                }
+                !remove
+            });
+            for stat in changed {
+                self.recompute(stat);
+            }
+        }
+        fn recompute(&mut self, stat: Stat) {
+            self.value[stat as usize] = self.base(stat).map(|base| {
+                stat_value(
+                    base,
+                    self.modifiers
+                        .iter()
+                        .filter(|modifier| modifier.stat == stat),
+                )
+            });
+        }
+    }
+}
+
+pub(super) mod systems {
+    use bevy::ecs::entity::Entities;
+
+    use super::*;
+    use components::*;
+
+    pub fn add_stats_to_unit(
+        trigger: Trigger<OnAdd, Unit>,
+        mut commands: Commands,
+        query: Query<(), Without<Stats>>,
+    ) {
+        let entity = trigger.entity();
+        if query.contains(entity) {
+            commands.entity(entity).insert(Stats::default());
+        }
+    }
+
+    /// Drop the modifiers granted by despawned sources, e.g. the buffs of a despawned caster.
+    pub fn drop_modifiers_of_despawned_sources(mut query: Query<&mut Stats>, entities: &Entities) {
+        for mut stats in query.iter_mut() {
+            let despawned = |modifier: &StatModifier| !entities.contains(modifier.source);
+            // Only flagged as changed if there is anything to drop.
+            if stats.modifiers().iter().any(despawned) {
+                stats.remove_where(despawned);
+            }
+        }
+    }
+
+    /// Write final stat values into the components they drive.
+    #[allow(clippy::type_complexity)]
+    pub fn write_stats(
+        mut query: Query<
+            (
+                &mut Stats,
+                Option<&mut HP>,
+                Option<&mut SelfMoving>,
+                Option<&mut Radius>,
+                Option<&mut CrossSectionSize>,
+                Option<&mut Mass>,
+                Option<&mut Mesh2dHandle>,
+            ),
+            // Self-movement is given back on respawn, with its old value.
+            Or<(Changed<Stats>, Added<SelfMoving>)>,
+        >,
+        mut cache: ResMut<UnitRenderCache>,
+        mut meshes: ResMut<Assets<Mesh>>,
+    ) {
+        for (mut stats, hp, self_moving, radius, cross_section, mass, mesh) in query.iter_mut() {
+            // Not a change of the stats themselves, so it should not trigger this again.
+            let stats = stats.bypass_change_detection();
+            let mut adopt = |stat, current: Option<f32>| {
+                if stats.base(stat).is_none() {
+                    if let Some(current) = current {
+                        stats.set_base(stat, current);
+                    }
+                }
+            };
+            adopt(Stat::MaxHp, hp.as_deref().map(|hp| hp.max));
+            adopt(
+                Stat::Accel,
+                self_moving.as_deref().map(|moving| moving.accel),
+            );
+            adopt(Stat::Radius, radius.as_deref().map(|radius| radius.0));
+            adopt(Stat::Mass, mass.as_deref().map(|mass| mass.0));
+            adopt(Stat::Damage, Some(1.));
+
+            if let (Some(mut hp), Some(max)) = (hp, stats.get(Stat::MaxHp)) {
+                if hp.max != max {
+                    let fraction = if hp.max > 0. { hp.value / hp.max } else { 1. };
+                    *hp = HP::new(fraction * max, max);
+                }
+            }
+            if let (Some(mut self_moving), Some(accel)) = (self_moving, stats.get(Stat::Accel)) {
+                self_moving.accel = accel;
+            }
+            if let (Some(mut radius), Some(value)) = (radius, stats.get(Stat::Radius)) {
+                if radius.0 != value {
+                    radius.0 = value;
+                    if let Some(mut cross_section) = cross_section {
+                        cross_section.0 = 2. * value;
+                    }
+                    if let Some(mut mesh) = mesh {
+                        *mesh = cache.mesh(value, &mut meshes);
+                    }
+                }
+            }
+            if let (Some(mut mass), Some(value)) = (mass, stats.get(Stat::Mass)) {
+                mass.0 = value;
+            }
+        }
+    }
+}
+
+pub mod prelude {
+    pub use super::components::*;
+
+    pub use super::stat_value;
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    use components::*;
+
+    fn world() -> World {
+        let mut world = World::new();
+        world.init_resource::<UnitRenderCache>();
+        world.init_resource::<Assets<Mesh>>();
+        world
+    }
+
+    #[test]
+    fn modifier_layers() {
+        let item = Entity::from_raw(1);
+        let buff = Entity::from_raw(2);
+        let mut stats = Stats::default();
+        stats.add(StatModifier::flat(Stat::MaxHp, 20., item));
+        assert_eq!(stats.get(Stat::MaxHp), None);
+
+        stats.set_base(Stat::MaxHp, 100.);
+        stats.add(StatModifier::percent(Stat::MaxHp, 0.25, buff));
+        stats.add(StatModifier::percent(Stat::MaxHp, 0.25, item).named("enchant"));
+        stats.add(StatModifier::multiply(Stat::MaxHp, 0.5, buff));
+        assert_eq!(stats.get(Stat::MaxHp), Some(90.));
+
+        // Same stat, source and name: replaced rather than stacked.
+        stats.add(StatModifier::multiply(Stat::MaxHp, 2., buff));
+        assert_eq!(stats.get(Stat::MaxHp), Some(360.));
+
+        stats.remove_source(item);
+        assert_eq!(stats.get(Stat::MaxHp), Some(250.));
+        stats.remove(Stat::MaxHp, buff, "");
+        assert_eq!(stats.get(Stat::MaxHp), Some(100.));
+    }
+
+    #[test]
+    fn written_into_components() {
+        let mut world = world();
+        let unit = world
+            .spawn((
+                Stats::default(),
+                HP::new(50., 100.),
+                SelfMoving { accel: 10. },
+                Radius(5.),
+                CrossSectionSize(10.),
+                Mass(2.),
+                Mesh2dHandle::default(),
+            ))
+            .id();
+        world.run_system_once(systems::write_stats);
+
+        // Bases adopted from the components, which are left as they were.
+        let stats = world.get::<Stats>(unit).unwrap();
+        assert_eq!(stats.base(Stat::MaxHp), Some(100.));
+        assert_eq!(stats.base(Stat::Accel), Some(10.));
+        assert_eq!(stats.base(Stat::Radius), Some(5.));
+        assert_eq!(stats.base(Stat::Mass), Some(2.));
+        assert_eq!(stats.get(Stat::Damage), Some(1.));
+        assert_eq!(*world.get::<HP>(unit).unwrap(), HP::new(50., 100.));
+
+        let mut stats = world.get_mut::<Stats>(unit).unwrap();
+        stats.add(StatModifier::percent(Stat::MaxHp, 1., unit));
+        stats.add(StatModifier::flat(Stat::Accel, 5., unit));
+        stats.add(StatModifier::multiply(Stat::Radius, 2., unit));
+        stats.add(StatModifier::flat(Stat::Mass, 1., unit));
+        world.run_system_once(systems::write_stats);
+
+        let entity = world.entity(unit);
+        // Keeping the fraction of HP left.
+        assert_eq!(*entity.get::<HP>().unwrap(), HP::new(100., 200.));
+        assert_eq!(entity.get::<SelfMoving>().unwrap().accel, 15.);
+        assert_eq!(entity.get::<Radius>().unwrap().0, 10.);
+        assert_eq!(entity.get::<CrossSectionSize>().unwrap().0, 20.);
+        assert_eq!(entity.get::<Mass>().unwrap().0, 3.);
+        assert_ne!(
+            *entity.get::<Mesh2dHandle>().unwrap(),
+            Mesh2dHandle::default()
+        );
+    }
+
+    #[test]
+    fn despawned_sources_lose_their_modifiers() {
+        let mut world = world();
+        let caster = world.spawn_empty().id();
+        let item = world.spawn_empty().id();
+        let mut stats = Stats::default();
+        stats.add(StatModifier::flat(Stat::Accel, 5., caster));
+        stats.add(StatModifier::flat(Stat::Accel, 1., item));
+        let unit = world.spawn((stats, SelfMoving { accel: 10. })).id();
+        world.run_system_once(systems::write_stats);
+        assert_eq!(world.get::<SelfMoving>(unit).unwrap().accel, 16.);
+
+        world.despawn(caster);
+        world.run_system_once(systems::drop_modifiers_of_despawned_sources);
+        world.run_system_once(systems::write_stats);
+        assert_eq!(world.get::<SelfMoving>(unit).unwrap().accel, 11.);
+        assert_eq!(world.get::<Stats>(unit).unwrap().modifiers().len(), 1);
+    }
+}
@@ -0,0 +1,660 @@
+//! Status effects: timed buffs and debuffs with stacking rules and periodic ticks.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use super::super::allegience::prelude::*;
+use super::super::kinematic::prelude::*;
+use super::components::*;
+use super::damage::components::DamageKind;
+use super::damage::events::DealDamage;
+use super::death::components::Dead;
+use super::events::UnitHealed;
+use super::stats::components::{Stat, StatModifier, Stats};
+
+/// Name of the modifier of [`Stat::Accel`] from slows and hastes.
+const SPEED_MODIFIER: &str = "status";
+
+pub mod components {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum StatusKind {
+        /// Multiplies [`Stat::Accel`] by `1 - fraction`, per stack.
+        Slow(f32),
+        /// Multiplies [`Stat::Accel`] by `1 + fraction`, per stack.
+        Haste(f32),
+        /// Damage per tick and stack, through [`DealDamage`].
+        DamageOverTime { amount: f32, kind: DamageKind },
+        /// Healing per tick and stack.
+        HealOverTime(f32),
+        /// No movement nor weapons.
+        Stun,
+        /// No movement.
+        Root,
+        /// Grants [`Invulnerability`].
+        Invulnerable,
+        /// No built-in behaviour, for other systems to read from [`StatusEffects`].
+        Custom,
+    }
+
+    /// What happens when an effect is applied to a unit already having one of the same name.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub enum Stacking {
+        /// Replace the effect and restart its duration.
+        #[default]
+        Refresh,
+        /// Add a stack, up to `max`, and restart the duration.
+        Stack { max: u32 },
+        /// Keep both, each with its own duration.
+        Independent,
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct StatusEffect {
+        /// Effects of the same name stack with each other.
+        pub name: &'static str,
+        pub kind: StatusKind,
+        pub stacking: Stacking,
+        pub duration: Timer,
+        /// Period of over-time effects.
+        pub interval: Option<Timer>,
+        pub stacks: u32,
+        pub source: Option<Entity>,
+        /// Faction of the source when applied, which may be gone by the time the effect ticks.
+        pub source_faction: Option<Faction>,
+    }
+    impl StatusEffect {
+        pub fn new(name: &'static str, kind: StatusKind, duration: Duration) -> Self {
+            Self {
+                name,
+                kind,
+                stacking: Stacking::default(),
+                duration: Timer::new(duration, TimerMode::Once),
+                interval: None,
+                stacks: 1,
+                source: None,
+                source_faction: None,
+            }
+        }
+        pub fn slow(fraction: f32, duration: Duration) -> Self {
+            Self::new("slow", StatusKind::Slow(fraction), duration)
+        }
+        pub fn haste(fraction: f32, duration: Duration) -> Self {
+            Self::new("haste", StatusKind::Haste(fraction), duration)
+        }
+        pub fn damage_over_time(
+            amount: f32,
+            kind: DamageKind,
+            interval: Duration,
+            duration: Duration,
+        ) -> Self {
+            Self::new(
+                "damage_over_time",
+                StatusKind::DamageOverTime { amount, kind },
+                duration,
+            )
+            .every(interval)
+        }
+        pub fn heal_over_time(amount: f32, interval: Duration, duration: Duration) -> Self {
+            Self::new("heal_over_time", StatusKind::HealOverTime(amount), duration).every(interval)
+        }
+        pub fn stun(duration: Duration) -> Self {
+            Self::new("stun", StatusKind::Stun, duration)
+        }
+        pub fn root(duration: Duration) -> Self {
+            Self::new("root", StatusKind::Root, duration)
+        }
+        pub fn invulnerable(duration: Duration) -> Self {
+            Self::new("invulnerable", StatusKind::Invulnerable, duration)
+        }
+
+        pub fn named(mut self, name: &'static str) -> Self {
+            self.name = name;
+            self
+        }
+        pub fn with_stacking(mut self, stacking: Stacking) -> Self {
+            self.stacking = stacking;
+            self
+        }
+        pub fn from(mut self, source: Entity, faction: Option<Faction>) -> Self {
+            self.source = Some(source);
+            self.source_faction = faction;
+            self
+        }
+        pub fn every(mut self, interval: Duration) -> Self {
+            self.interval = Some(Timer::new(interval, TimerMode::Repeating));
+            self
+        }
+
+        /// Advance the effect, returning how many times it ticked.
+        pub fn tick(&mut self, delta: Duration) -> u32 {
+            self.duration.tick(delta);
+            self.interval.as_mut().map_or(0, |interval| {
+                interval.tick(delta).times_finished_this_tick()
+            })
+        }
+        pub fn is_expired(&self) -> bool {
+            self.duration.finished()
+        }
+    }
+
+    /// The status effects active on a unit.
+    #[derive(Debug, Clone, PartialEq, Default, Component)]
+    pub struct StatusEffects(pub(super) Vec<StatusEffect>);
+    impl StatusEffects {
+        pub fn iter(&self) -> impl Iterator<Item = &StatusEffect> {
+            self.0.iter()
+        }
+        pub fn has(&self, name: &str) -> bool {
+            self.0.iter().any(|effect| effect.name == name)
+        }
+        /// Total stacks of the effects of this name.
+        pub fn stacks(&self, name: &str) -> u32 {
+            self.0
+                .iter()
+                .filter(|effect| effect.name == name)
+                .map(|effect| effect.stacks)
+                .sum()
+        }
+
+        /// Add an effect, following its [`Stacking`].
+        pub fn add(&mut self, effect: StatusEffect) {
+            let existing = match effect.stacking {
+                Stacking::Independent => None,
+                _ => self.0.iter_mut().find(|other| other.name == effect.name),
+            };
+            let Some(existing) = existing else {
+                self.0.push(effect);
+                return;
+            };
+            if let Stacking::Stack { max } = effect.stacking {
+                existing.stacks = (existing.stacks + 1).min(max.max(1));
+            }
+            // The tick period carries over, so reapplying never delays a tick.
+            existing.kind = effect.kind;
+            existing.stacking = effect.stacking;
+            existing.duration = effect.duration;
+            if effect.source.is_some() {
+                existing.source = effect.source;
+                existing.source_faction = effect.source_faction;
+            }
+        }
+
+        /// Multiplier of [`Stat::Accel`] from slows and hastes.
+        pub fn speed_multiplier(&self) -> f32 {
+            self.0
+                .iter()
+                .map(|effect| match effect.kind {
+                    StatusKind::Slow(fraction) => {
+                        (1. - fraction).max(0.).powi(effect.stacks as i32)
+                    }
+                    StatusKind::Haste(fraction) => (1. + fraction).powi(effect.stacks as i32),
+                    _ => 1.,
+                })
+                .product()
+        }
+
+        /// Whether an effect is of exactly this kind.
+        pub fn any(&self, kind: StatusKind) -> bool {
+            self.0.iter().any(|effect| effect.kind == kind)
+        }
+    }
+
+    /// Tags a stunned unit. Maintained from its [`StatusEffects`].
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Component)]
+    pub struct Stunned;
+
+    /// Tags a rooted unit. Maintained from its [`StatusEffects`].
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Component)]
+    pub struct Rooted;
+}
+
+pub mod events {
+    use super::*;
+
+    use components::*;
+
+    #[derive(Debug, Clone, PartialEq, Event)]
+    pub struct ApplyStatus {
+        pub target: Entity,
+        pub effect: StatusEffect,
+    }
+
+    /// Remove every effect of this name from the target.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Event)]
+    pub struct DispelStatus {
+        pub target: Entity,
+        pub name: &'static str,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum StatusEnd {
+        Expired,
+        Dispelled,
+        Died,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Event)]
+    pub struct StatusRemoved {
+        pub entity: Entity,
+        pub effect: StatusEffect,
+        pub reason: StatusEnd,
+    }
+}
+
+pub(super) mod systems {
+    use super::*;
+    use components::*;
+    use events::*;
+
+    pub fn apply_status_events(
+        mut commands: Commands,
+        mut applied: EventReader<ApplyStatus>,
+        mut dispelled: EventReader<DispelStatus>,
+        mut query: Query<Option<&mut StatusEffects>, (With<Unit>, Without<Dead>)>,
+        mut removed: EventWriter<StatusRemoved>,
+    ) {
+        // Units getting their first effects, possibly several in the same frame.
+        let mut new: HashMap<Entity, StatusEffects> = HashMap::new();
+        for ApplyStatus { target, effect } in applied.read() {
+            match query.get_mut(*target) {
+                Ok(Some(mut effects)) => effects.add(effect.clone()),
+                Ok(None) => new.entry(*target).or_default().add(effect.clone()),
+                Err(_) => {}
+            }
+        }
+        for (entity, effects) in new {
+            commands.entity(entity).insert(effects);
+        }
+
+        for &DispelStatus { target, name } in dispelled.read() {
+            let Ok(Some(mut effects)) = query.get_mut(target) else {
+                continue;
+            };
+            let (gone, kept) = std::mem::take(&mut effects.0)
+                .into_iter()
+                .partition(|effect| effect.name == name);
+            effects.0 = kept;
+            removed.send_batch(gone.into_iter().map(|effect| StatusRemoved {
+                entity: target,
+                effect,
+                reason: StatusEnd::Dispelled,
+            }));
+        }
+    }
+
+    pub fn tick_status_effects(
+        mut query: Query<(Entity, &mut StatusEffects, Option<&mut HP>), Without<Dead>>,
+        mut damage: EventWriter<DealDamage>,
+        mut healed: EventWriter<UnitHealed>,
+        mut removed: EventWriter<StatusRemoved>,
+        time: Res<Time>,
+    ) {
+        for (entity, mut effects, mut hp) in query.iter_mut() {
+            // Only flagged as changed once an effect expires, not on every tick.
+            for effect in effects.bypass_change_detection().0.iter_mut() {
+                let ticks = effect.tick(time.delta());
+                if ticks == 0 {
+                    continue;
+                }
+                let amount = ticks as f32 * effect.stacks as f32;
+                match effect.kind {
+                    StatusKind::DamageOverTime {
+                        amount: per_tick,
+                        kind,
+                    } => {
+                        let mut request = DealDamage::new(entity, per_tick * amount).of_kind(kind);
+                        request.source = effect.source;
+                        request.source_faction = effect.source_faction;
+                        damage.send(request);
+                    }
+                    StatusKind::HealOverTime(per_tick) => {
+                        if let Some(hp) = hp.as_mut() {
+                            let before = hp.value;
+                            hp.heal(per_tick * amount);
+                            if hp.value > before {
+                                healed.send(UnitHealed {
+                                    entity,
+                                    amount: hp.value - before,
+                                });
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            if effects.0.iter().any(StatusEffect::is_expired) {
+                let (gone, kept) = std::mem::take(&mut effects.0)
+                    .into_iter()
+                    .partition(StatusEffect::is_expired);
+                effects.0 = kept;
+                removed.send_batch(gone.into_iter().map(|effect| StatusRemoved {
+                    entity,
+                    effect,
+                    reason: StatusEnd::Expired,
+                }));
+            }
+        }
+    }
+
+    /// Keep the markers and the speed modifier of units in line with their effects.
+    #[allow(clippy::type_complexity)]
+    pub fn update_status_markers(
+        mut commands: Commands,
+        mut query: Query<
+            (
+                Entity,
+                &StatusEffects,
+                Option<&mut Stats>,
+                Has<Stunned>,
+                Has<Rooted>,
+                Has<Invulnerability>,
+            ),
+            (Changed<StatusEffects>, Without<Dead>),
+        >,
+    ) {
+        for (entity, effects, stats, was_stunned, was_rooted, was_invulnerable) in query.iter_mut()
+        {
+            let mut entity_commands = commands.entity(entity);
+            let stunned = effects.any(StatusKind::Stun);
+            let rooted = effects.any(StatusKind::Root);
+            let invulnerable = effects.any(StatusKind::Invulnerable);
+
+            match (stunned, was_stunned) {
+                (true, false) => {
+                    entity_commands.insert(Stunned);
+                }
+                (false, true) => {
+                    entity_commands.remove::<Stunned>();
+                }
+                _ => {}
+            }
+            match (rooted, was_rooted) {
+                (true, false) => {
+                    entity_commands.insert(Rooted);
+                }
+                (false, true) => {
+                    entity_commands.remove::<Rooted>();
+                }
+                _ => {}
+            }
+            if stunned || rooted {
+                entity_commands.insert(Immobilized);
+            } else if was_stunned || was_rooted {
+                entity_commands.remove::<Immobilized>();
+            }
+            match (invulnerable, was_invulnerable) {
+                (true, false) => {
+                    entity_commands.insert(Invulnerability);
+                }
+                (false, true) => {
+                    entity_commands.remove::<Invulnerability>();
+                }
+                _ => {}
+            }
+
+            let Some(mut stats) = stats else {
+                continue;
+            };
+            let multiplier = effects.speed_multiplier();
+            let wanted = (multiplier != 1.).then_some(multiplier);
+            let current = stats
+                .modifier(Stat::Accel, entity, SPEED_MODIFIER)
+                .map(|modifier| modifier.value);
+            if current != wanted {
+                match wanted {
+                    Some(multiplier) => stats.add(
+                        StatModifier::multiply(Stat::Accel, multiplier, entity)
+                            .named(SPEED_MODIFIER),
+                    ),
+                    None => stats.remove(Stat::Accel, entity, SPEED_MODIFIER),
+                }
+            }
+        }
+    }
+
+    /// Dead units lose all their effects.
+    pub fn clear_status_of_dead(
+        mut commands: Commands,
+        mut query: Query<(Entity, &mut StatusEffects, Option<&mut Stats>), Added<Dead>>,
+        mut removed: EventWriter<StatusRemoved>,
+    ) {
+        for (entity, mut effects, stats) in query.iter_mut() {
+            removed.send_batch(effects.0.drain(..).map(|effect| StatusRemoved {
+                entity,
+                effect,
+                reason: StatusEnd::Died,
+            }));
+            commands
+                .entity(entity)
+                .remove::<(Stunned, Rooted, Immobilized, Invulnerability)>();
+            if let Some(mut stats) = stats {
+                stats.remove(Stat::Accel, entity, SPEED_MODIFIER);
+            }
+        }
+    }
+}
+
+pub mod prelude {
+    pub use super::components::*;
+    pub use super::events::*;
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    use components::*;
+    use events::*;
+
+    const SECOND: Duration = Duration::from_secs(1);
+
+    fn world() -> World {
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world.init_resource::<Events<ApplyStatus>>();
+        world.init_resource::<Events<DispelStatus>>();
+        world.init_resource::<Events<StatusRemoved>>();
+        world.init_resource::<Events<DealDamage>>();
+        world.init_resource::<Events<UnitHealed>>();
+        world
+    }
+
+    fn apply(world: &mut World, target: Entity, effects: impl IntoIterator<Item = StatusEffect>) {
+        for effect in effects {
+            world.send_event(ApplyStatus { target, effect });
+        }
+        world.run_system_once(systems::apply_status_events);
+        world.resource_mut::<Events<ApplyStatus>>().clear();
+    }
+
+    /// Advance the time and run the status systems, returning the effects removed.
+    fn frame(world: &mut World, delta: Duration) -> Vec<(&'static str, StatusEnd)> {
+        world.resource_mut::<Time>().advance_by(delta);
+        world.run_system_once(systems::tick_status_effects);
+        world.run_system_once(systems::update_status_markers);
+        world.run_system_once(systems::clear_status_of_dead);
+        world
+            .resource_mut::<Events<StatusRemoved>>()
+            .drain()
+            .map(|removed| (removed.effect.name, removed.reason))
+            .collect()
+    }
+
+    #[test]
+    fn stacking_rules() {
+        let second = Duration::from_secs(1);
+        let mut effects = StatusEffects::default();
+
+        effects.add(StatusEffect::slow(0.5, second));
+        effects.0[0].tick(second / 2);
+        effects.add(StatusEffect::slow(0.2, second));
+        assert_eq!(effects.stacks("slow"), 1);
+        assert_eq!(effects.0[0].duration.elapsed(), Duration::ZERO);
+        assert_eq!(effects.speed_multiplier(), 0.8);
+
+        let poison = StatusEffect::damage_over_time(1., DamageKind::Poison, second, second)
+            .with_stacking(Stacking::Stack { max: 2 });
+        for _ in 0..3 {
+            effects.add(poison.clone());
+        }
+        assert_eq!(effects.stacks("damage_over_time"), 2);
+
+        let burn = StatusEffect::damage_over_time(1., DamageKind::Fire, second, second)
+            .named("burn")
+            .with_stacking(Stacking::Independent);
+        effects.add(burn.clone());
+        effects.add(burn);
+        assert_eq!(
+            effects
+                .iter()
+                .filter(|effect| effect.name == "burn")
+                .count(),
+            2
+        );
+
+        // Two ticks within a single long frame.
+        let mut regen = StatusEffect::heal_over_time(1., second, second * 3);
+        assert_eq!(regen.tick(second * 2), 2);
+        assert!(!regen.is_expired());
+        assert_eq!(regen.tick(second), 1);
+        assert!(regen.is_expired());
+    }
+
+    #[test]
+    fn ticks_until_expired() {
+        let mut world = world();
+        let source = world.spawn_empty().id();
+        let unit = world.spawn((Unit, HP::new(5., 10.))).id();
+        let poison = StatusEffect::damage_over_time(2., DamageKind::Poison, SECOND, SECOND * 2)
+            .with_stacking(Stacking::Stack { max: 2 })
+            .from(source, None);
+        apply(
+            &mut world,
+            unit,
+            [
+                poison.clone(),
+                poison,
+                StatusEffect::heal_over_time(1., SECOND, SECOND * 3),
+            ],
+        );
+
+        assert!(frame(&mut world, SECOND).is_empty());
+        let damage: Vec<_> = world
+            .resource_mut::<Events<DealDamage>>()
+            .drain()
+            .map(|request| (request.target, request.amount, request.kind, request.source))
+            .collect();
+        assert_eq!(damage, vec![(unit, 4., DamageKind::Poison, Some(source))]);
+        assert_eq!(world.get::<HP>(unit).unwrap().value, 6.);
+        let healed: Vec<_> = world
+            .resource_mut::<Events<UnitHealed>>()
+            .drain()
+            .map(|healed| healed.amount)
+            .collect();
+        assert_eq!(healed, vec![1.]);
+
+        // Ticks one last time as it expires.
+        assert_eq!(
+            frame(&mut world, SECOND),
+            vec![("damage_over_time", StatusEnd::Expired)]
+        );
+        assert_eq!(world.resource::<Events<DealDamage>>().len(), 1);
+        assert_eq!(world.get::<HP>(unit).unwrap().value, 7.);
+        assert_eq!(
+            frame(&mut world, SECOND),
+            vec![("heal_over_time", StatusEnd::Expired)]
+        );
+        assert_eq!(world.get::<HP>(unit).unwrap().value, 8.);
+    }
+
+    #[test]
+    fn stuns_and_roots_immobilize() {
+        let mut world = world();
+        let unit = world.spawn(Unit).id();
+        apply(
+            &mut world,
+            unit,
+            [StatusEffect::stun(SECOND), StatusEffect::root(SECOND * 2)],
+        );
+
+        assert!(frame(&mut world, Duration::ZERO).is_empty());
+        let markers = |world: &World| {
+            let unit = world.entity(unit);
+            (
+                unit.contains::<Stunned>(),
+                unit.contains::<Rooted>(),
+                unit.contains::<Immobilized>(),
+            )
+        };
+        assert_eq!(markers(&world), (true, true, true));
+
+        assert_eq!(
+            frame(&mut world, SECOND),
+            vec![("stun", StatusEnd::Expired)]
+        );
+        assert_eq!(markers(&world), (false, true, true));
+
+        assert_eq!(
+            frame(&mut world, SECOND),
+            vec![("root", StatusEnd::Expired)]
+        );
+        assert_eq!(markers(&world), (false, false, false));
+    }
+
+    #[test]
+    fn cleared_on_death() {
+        let mut world = world();
+        let unit = world.spawn(Unit).id();
+        apply(
+            &mut world,
+            unit,
+            [
+                StatusEffect::invulnerable(SECOND),
+                StatusEffect::stun(SECOND),
+            ],
+        );
+        frame(&mut world, Duration::ZERO);
+        assert!(world.get::<Invulnerability>(unit).is_some());
+
+        world.entity_mut(unit).insert(Dead);
+        assert_eq!(
+            frame(&mut world, Duration::ZERO),
+            vec![("invulnerable", StatusEnd::Died), ("stun", StatusEnd::Died)]
+        );
+        let unit = world.entity(unit);
+        assert!(!unit.contains::<Invulnerability>());
+        assert!(!unit.contains::<Stunned>());
+        assert!(!unit.contains::<Immobilized>());
+        assert!(unit.get::<StatusEffects>().unwrap().0.is_empty());
+    }
+
+    #[test]
+    fn changed_only_on_expiry() {
+        let mut world = world();
+        let unit = world.spawn(Unit).id();
+        apply(
+            &mut world,
+            unit,
+            [StatusEffect::heal_over_time(1., SECOND, SECOND * 2)],
+        );
+        let mut changed = |world: &mut World, delta| {
+            world.clear_trackers();
+            frame(world, delta);
+            world
+                .entity(unit)
+                .get_ref::<StatusEffects>()
+                .unwrap()
+                .is_changed()
+        };
+
+        assert!(!changed(&mut world, SECOND));
+        assert!(changed(&mut world, SECOND));
+    }
+}